@@ -1,11 +1,19 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize as De, Serialize as Ser};
 use thiserror::Error as DeriveError;
 
 use crate::{calendar::Calendar,
+            handle::idempotency,
             integrate,
+            lamb::ScheduleKind,
             notify,
             notify::Notifier,
             prelude::*,
+            time::WorkHours,
+            webhook,
             AnyError};
 
 pub mod state;
@@ -56,6 +64,63 @@ pub struct App {
 
   // ms
   pub ms_graph_base_url: String,
+
+  // inbound http auth
+  #[serde(default)]
+  pub http_auth_token: Option<String>,
+  #[serde(default)]
+  pub http_auth_hmac_secret: Option<String>,
+
+  // cors
+  #[serde(default)]
+  pub cors_allowed_origins: Vec<String>,
+  #[serde(default)]
+  pub cors_allowed_methods: Vec<String>,
+  #[serde(default)]
+  pub cors_allowed_headers: Vec<String>,
+
+  // eventbridge rule ARN or name -> what to do when it fires
+  #[serde(default)]
+  pub schedule_rules: HashMap<String, ScheduleKind>,
+
+  // job name -> last successful run
+  #[serde(default)]
+  pub job_runs: HashMap<String, DateTime<Utc>>,
+
+  // github webhooks
+  #[serde(default)]
+  pub github_webhook_secret: Option<String>,
+  #[serde(default)]
+  pub github_events: Vec<String>,
+  #[serde(default)]
+  pub github_branches: Vec<String>,
+  #[serde(default)]
+  pub github_username: Option<String>,
+
+  // webhook name -> how to turn its payloads into notifications
+  #[serde(default)]
+  pub webhook_rules: HashMap<String, webhook::Rule>,
+
+  // idempotency key -> stored response
+  #[serde(default)]
+  pub idempotency: HashMap<String, idempotency::Record>,
+  #[serde(default)]
+  pub idempotency_ttl_secs: Option<u32>,
+
+  // template name -> source, see `crate::template`
+  #[serde(default)]
+  pub templates: HashMap<String, String>,
+
+  // IANA timezone that times are shown in and days are counted in
+  #[serde(default = "App::default_timezone")]
+  pub timezone: Tz,
+
+  // free time in working hours at least this long is reported
+  // as focus time in summaries
+  #[serde(default)]
+  pub work_hours: WorkHours,
+  #[serde(default)]
+  pub focus_min_minutes: Option<u32>,
 }
 
 impl App {
@@ -73,11 +138,33 @@ impl App {
                      ms_graph_base_url: String::new(),
                      pushbullet_base_url: String::new(),
                      pushbullet_token: String::new(),
-                     integrate_ad_auth: auth_empty };
+                     integrate_ad_auth: auth_empty,
+                     http_auth_token: None,
+                     http_auth_hmac_secret: None,
+                     cors_allowed_origins: vec![],
+                     cors_allowed_methods: vec![],
+                     cors_allowed_headers: vec![],
+                     schedule_rules: HashMap::new(),
+                     job_runs: HashMap::new(),
+                     github_webhook_secret: None,
+                     github_events: vec![],
+                     github_branches: vec![],
+                     github_username: None,
+                     webhook_rules: HashMap::new(),
+                     idempotency: HashMap::new(),
+                     idempotency_ttl_secs: None,
+                     templates: HashMap::new(),
+                     timezone: Self::default_timezone(),
+                     work_hours: WorkHours::default(),
+                     focus_min_minutes: None };
 
     Ok(app)
   }
 
+  pub fn default_timezone() -> Tz {
+    chrono_tz::America::Denver
+  }
+
   fn init_integrate_ad_auth(&mut self) -> () {
     match self.integrate_ad_auth {
       | integrate::ad::Auth::NotAuthed { .. } => {
@@ -93,6 +180,13 @@ impl App {
     }
   }
 
+  /// Replace the Integrate AD auth, rebuilding the calendars that use it
+  pub fn set_integrate_ad_auth(&mut self, auth: integrate::ad::Auth) {
+    self.integrate_ad_auth = auth;
+    self.calendars = vec![];
+    self.add_calendar_integrate();
+  }
+
   fn add_calendar_integrate(&mut self) -> () {
     let outlook = integrate::Outlook::new(self.integrate_ad_auth.clone());
 
@@ -109,14 +203,21 @@ impl App {
         .push(Box::from(pb_notifier) as Box<dyn DebugNotifier>);
   }
 
+  /// Long enough for pooled connections to survive between `KeepWarm` ticks
+  fn reqw_idle_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(6 * 60)
+  }
+
   fn init_reqw() -> Result<reqwest::Client, Error> {
     reqwest::Client::builder().use_rustls_tls()
+                              .pool_idle_timeout(Self::reqw_idle_timeout())
                               .build()
                               .map_err(Error::Reqwest)
   }
 
   fn init_reqw_panic() -> reqwest::Client {
     reqwest::Client::builder().use_rustls_tls()
+                              .pool_idle_timeout(Self::reqw_idle_timeout())
                               .build()
                               .map_err(Error::Reqwest)
                               .tap_err(|e| log::error!("{:#?}", e))
@@ -32,6 +32,38 @@ impl State {
       };
     }
 
+    macro_rules! set_from_env_opt {
+      ($k:ident) => {
+        state.$k = env::var(std::stringify!($k).to_uppercase()).ok();
+      };
+      ($k:ident, $parse:expr) => {
+        if let Ok(v) = env::var(std::stringify!($k).to_uppercase()) {
+          state.$k = $parse(v);
+        }
+      };
+    }
+
+    set_from_env_opt!(http_auth_token);
+    set_from_env_opt!(http_auth_hmac_secret);
+    set_from_env_opt!(cors_allowed_origins, split_list);
+    set_from_env_opt!(cors_allowed_methods, split_list);
+    set_from_env_opt!(cors_allowed_headers, split_list);
+    set_from_env_opt!(schedule_rules, parse_json);
+    set_from_env_opt!(github_webhook_secret);
+    set_from_env_opt!(github_events, split_list);
+    set_from_env_opt!(github_branches, split_list);
+    set_from_env_opt!(github_username);
+    set_from_env_opt!(webhook_rules, parse_json);
+    set_from_env_opt!(idempotency_ttl_secs, parse_json);
+    set_from_env_opt!(timezone, parse_tz);
+    set_from_env_opt!(templates, parse_json);
+    set_from_env_opt!(work_hours, parse_work_hours);
+    set_from_env_opt!(focus_min_minutes, parse_json);
+
+    if let Ok(path) = env::var("TEMPLATES_PATH") {
+      state.templates.extend(read_templates(&path));
+    }
+
     let results = vec![set_from_env!(integrate_ad_client_id),
                        set_from_env!(pushbullet_token),
                        set_from_env!(pushbullet_base_url),
@@ -58,6 +90,51 @@ impl State {
   }
 }
 
+/// Parse a comma-separated environment variable
+fn split_list(v: String) -> Vec<String> {
+  v.split(',')
+   .map(str::trim)
+   .filter(|s| !s.is_empty())
+   .map(String::from)
+   .collect()
+}
+
+/// Parse an IANA timezone environment variable, falling back to the default
+fn parse_tz(v: String) -> chrono_tz::Tz {
+  v.trim()
+   .parse()
+   .tap_err(|e| log::error!("Invalid timezone in environment: {}", e))
+   .unwrap_or_else(|_| App::default_timezone())
+}
+
+/// Parse a working hours environment variable like `09:00-17:00`,
+/// falling back to the default
+fn parse_work_hours(v: String) -> crate::time::WorkHours {
+  v.parse()
+   .tap_err(|e| log::error!("Invalid working hours in environment: {}", e))
+   .unwrap_or_default()
+}
+
+/// Read a JSON file of templates by name, logging any error
+fn read_templates(path: &str) -> std::collections::HashMap<String, String> {
+  std::fs::read_to_string(path).norm()
+                               .bind(|json| serde_json::from_str(&json).norm())
+                               .tap_err(|e| {
+                                 log::error!("Failed to read {}: {:#?}",
+                                             path,
+                                             e)
+                               })
+                               .unwrap_or_default()
+}
+
+/// Parse a JSON environment variable, falling back to the default
+fn parse_json<T: serde::de::DeserializeOwned + Default>(v: String) -> T {
+  serde_json::from_str(&v).tap_err(|e| {
+                            log::error!("Invalid JSON in environment: {:#?}", e)
+                          })
+                          .unwrap_or_default()
+}
+
 #[async_trait]
 impl super::Modify for State {
   fn modify(&self,
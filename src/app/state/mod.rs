@@ -8,7 +8,7 @@ mod file;
 mod in_mem;
 
 use super::{App, Error};
-use crate::{prelude::*, AnyError};
+use crate::{notify::{Delivery, Priority}, prelude::*, AnyError};
 
 pub enum S {
   File,
@@ -21,8 +21,8 @@ pub trait Ext: Read + Modify {
     self.modify_async(|mut s| async {
       log::info!("Authenticating against Integrate AD...");
 
-      s.integrate_ad_auth =
-        s.integrate_ad_auth.authenticate(&s.reqw).await?;
+      let auth = s.integrate_ad_auth.clone().authenticate(&s.reqw).await?;
+      s.set_integrate_ad_auth(auth);
 
       let auth = &s.integrate_ad_auth;
 
@@ -92,6 +92,43 @@ pub trait Ext: Read + Modify {
       .map_err(super::Error::Many)
       .norm()
   }
+
+  /// Notify through every notifier, or only those named in `only`,
+  /// reporting how each delivery went rather than failing on the first
+  async fn notify_each(&self,
+                       title: &str,
+                       body: &str,
+                       priority: Priority,
+                       only: Option<&[String]>)
+                       -> Result<Vec<Delivery>, AnyError> {
+    let app = self.read().norm()?;
+    log::debug!("notify_each ({:?} of {} notifiers) - {}\n{}",
+                only,
+                app.notifiers.len(),
+                title,
+                body);
+
+    let targets = app.notifiers.iter().filter(|n| {
+      only.map(|only| only.iter().any(|o| o == n.name()))
+          .unwrap_or(true)
+    });
+
+    let deliveries = futures::stream::iter(targets)
+      .then(|n| async move {
+        let result = n.notify_priority(&app.reqw, title, body, priority)
+          .await
+          .tap(|_| log::info!("> {} success", n.name()))
+          .tap_err(|e| log::error!("> {} error: {:#?}", n.name(), e));
+
+        Delivery { notifier: n.name().to_string(),
+                   ok: result.is_ok(),
+                   error: result.err().map(|e| e.to_string()) }
+      })
+      .collect::<Vec<_>>()
+      .await;
+
+    Ok(deliveries)
+  }
 }
 
 impl<T: Read + Modify> Ext for T {}
@@ -1,17 +1,24 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize as Ser;
+use thiserror::Error as DeriveError;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Ser, JsonSchema)]
 pub struct Event {
   pub cat: Cat,
   pub title: String,
   pub time_start: DateTime<Utc>,
   pub time_end: DateTime<Utc>,
   pub location: Option<String>,
+
+  /// All-day events start and end at midnight UTC on the dates they
+  /// cover, whatever timezone they're shown in
+  pub all_day: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Ser, JsonSchema)]
 pub enum Cat {
   Work,
   Personal(Personal),
@@ -26,7 +33,7 @@ impl fmt::Display for Cat {
   }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Ser, JsonSchema)]
 pub enum Personal {
   Chore,
   Habit,
@@ -44,3 +51,78 @@ impl fmt::Display for Personal {
     })
   }
 }
+
+impl FromStr for Personal {
+  type Err = UnknownCat;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      | "chore" => Ok(Self::Chore),
+      | "habit" => Ok(Self::Habit),
+      | "plan" => Ok(Self::Plan),
+      | "med" | "medical" => Ok(Self::Med),
+      | _ => Err(UnknownCat(s.to_string())),
+    }
+  }
+}
+
+#[derive(Debug, DeriveError)]
+#[error("Unknown category {0:?}")]
+pub struct UnknownCat(String);
+
+/// Matches events by category, parsed from e.g. `work`, `personal`
+/// (any personal event) or `personal:chore`
+#[derive(Debug, PartialEq)]
+pub enum CatFilter {
+  Work,
+  Personal(Option<Personal>),
+}
+
+impl CatFilter {
+  pub fn matches(&self, cat: &Cat) -> bool {
+    match (self, cat) {
+      | (Self::Work, Cat::Work) => true,
+      | (Self::Personal(None), Cat::Personal(_)) => true,
+      | (Self::Personal(Some(want)), Cat::Personal(p)) => want == p,
+      | _ => false,
+    }
+  }
+}
+
+impl FromStr for CatFilter {
+  type Err = UnknownCat;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = s.trim().splitn(2, ':');
+    let cat = parts.next().unwrap_or_default().to_lowercase();
+
+    match (cat.as_str(), parts.next()) {
+      | ("work", None) => Ok(Self::Work),
+      | ("personal", None) => Ok(Self::Personal(None)),
+      | ("personal", Some(p)) => p.parse().map(|p| Self::Personal(Some(p))),
+      | _ => Err(UnknownCat(s.to_string())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  pub fn cat_filter_should_parse_and_match() {
+    // ACT
+    let work = "work".parse::<CatFilter>().unwrap();
+    let personal = "Personal".parse::<CatFilter>().unwrap();
+    let chores = "personal:chore".parse::<CatFilter>().unwrap();
+    let bad = "personal:nap".parse::<CatFilter>();
+
+    // ASSERT
+    assert!(work.matches(&Cat::Work));
+    assert!(!work.matches(&Cat::Personal(Personal::Chore)));
+    assert!(personal.matches(&Cat::Personal(Personal::Med)));
+    assert!(chores.matches(&Cat::Personal(Personal::Chore)));
+    assert!(!chores.matches(&Cat::Personal(Personal::Habit)));
+    assert!(bad.is_err());
+  }
+}
@@ -0,0 +1,132 @@
+//! Managing the Integrate AD device code flow over HTTP:
+//! - `GET /auth/status` shows where the flow is, without exposing tokens
+//! - `POST /auth/start` throws away the current auth and returns a new code
+//! - `DELETE /auth` signs out
+
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::Serialize as Ser;
+
+use super::{openapi::{self, Doc},
+            router,
+            HttpError,
+            State};
+use crate::{app::state::Ext,
+            integrate::ad::Auth,
+            lamb::HttpResponse,
+            prelude::*,
+            AnyError};
+
+/// Where the sign-in is at, without any tokens
+#[derive(Debug, Ser, JsonSchema)]
+struct Status {
+  state: String,
+  expires: Option<DateTime<Utc>>,
+  user: Option<String>,
+
+  /// Device code to enter, while waiting for one
+  code: Option<Code>,
+}
+
+#[derive(Debug, Ser, JsonSchema)]
+struct Code {
+  user_code: String,
+  url: Option<String>,
+  message: Option<String>,
+}
+
+impl Status {
+  fn new(auth: &Auth) -> Self {
+    Self { state: auth.dbg_label(),
+           expires: auth.expires(),
+           user: auth.user(),
+           code: Code::new(auth) }
+  }
+}
+
+impl Code {
+  fn new(auth: &Auth) -> Option<Self> {
+    let user_code = auth.user_code()?.to_string();
+
+    Some(Self { user_code,
+                url: auth.code_url().map(Into::into),
+                message: auth.wait_msg().map(Into::into) })
+  }
+}
+
+#[derive(Debug, Ser, JsonSchema)]
+struct Response {
+  success: bool,
+  auth: Status,
+}
+
+pub const STATUS_DOC: Doc = Doc::new("Where the Integrate AD sign-in is at")
+  .response(openapi::schema::<Response>);
+
+pub const START_DOC: Doc = Doc::new("Start a new Integrate AD sign-in")
+  .response(openapi::schema::<Response>);
+
+pub const SIGN_OUT_DOC: Doc =
+  Doc::new("Sign out of Integrate AD").response(openapi::schema::<Response>);
+
+/// Refresh the Integrate AD token for routes that read calendars, or fail
+/// if not signed in, since starting a sign-in pushes a code to the notifiers
+pub async fn require_authed(state: &impl State) -> Result<(), AnyError> {
+  match state.read().norm()?.integrate_ad_auth {
+    | Auth::Authed { .. } => state.authenticate_integrate().await,
+    | _ => {
+      let msg = "Not signed in to Integrate AD, see POST /auth/start";
+      Err(HttpError::Unavailable(msg.into()).into())
+    },
+  }
+}
+
+fn respond(auth: &Auth) -> Result<HttpResponse, AnyError> {
+  HttpResponse::new().body_json(Response { success: true,
+                                           auth: Status::new(auth) })
+                     .norm()
+}
+
+pub fn status<S: State>(state: &S,
+                        _: router::Req)
+                        -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move { respond(&state.read().norm()?.integrate_ad_auth) })
+}
+
+pub fn start<S: State>(state: &S,
+                       _: router::Req)
+                       -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    state.modify_async(|mut app| async {
+           log::info!("Restarting Integrate AD code flow");
+
+           let auth = app.integrate_ad_auth.clone().restart(&app.reqw).await?;
+           app.set_integrate_ad_auth(auth);
+
+           Ok(app)
+         })
+         .await
+         .norm()?;
+
+    respond(&state.read().norm()?.integrate_ad_auth)
+  })
+}
+
+pub fn sign_out<S: State>(state: &S,
+                          _: router::Req)
+                          -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    state.modify(|mut app| {
+           log::info!("Signing out of Integrate AD");
+
+           let auth = app.integrate_ad_auth.clone().reset();
+           app.set_integrate_ad_auth(auth);
+
+           Ok(app)
+         })
+         .norm()?;
+
+    respond(&state.read().norm()?.integrate_ad_auth)
+  })
+}
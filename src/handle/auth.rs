@@ -0,0 +1,207 @@
+//! Authentication of inbound HTTP requests.
+//!
+//! A request is accepted if it carries either:
+//! - `Authorization: Bearer <token>` matching `App::http_auth_token`, or
+//! - `X-Hooks-Timestamp: <unix seconds>` and
+//!   `X-Hooks-Signature: sha256=<hex>`, where the signature is the
+//!   HMAC-SHA256 of `{timestamp}.{METHOD}.{path}.{query}.{raw body}`
+//!   keyed with `App::http_auth_hmac_secret`. `{query}` is the query
+//!   parameters sorted by name and form-urlencoded, e.g. `a=1&b=x+y`,
+//!   or nothing if there are none.
+//!
+//! Signed requests whose timestamp is more than [`MAX_SKEW_SECS`] away from
+//! now are rejected, so a captured request can't be replayed later.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error as DeriveError;
+
+use crate::{app::App, lamb::HttpRequest};
+
+pub const TIMESTAMP_HEADER: &str = "X-Hooks-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Hooks-Signature";
+pub const MAX_SKEW_SECS: i64 = 5 * 60;
+
+#[derive(Debug, DeriveError)]
+pub enum Error {
+  #[error("No inbound authentication is configured")]
+  NotConfigured,
+
+  #[error("Request has no credentials")]
+  Missing,
+
+  #[error("Bearer token is invalid")]
+  BadToken,
+
+  #[error("Request signature is invalid")]
+  BadSignature,
+
+  #[error("Request timestamp is missing or malformed")]
+  BadTimestamp,
+
+  #[error("Request timestamp is outside of the allowed window")]
+  Expired,
+}
+
+pub fn authenticate(app: &App,
+                    req: &HttpRequest,
+                    now: DateTime<Utc>)
+                    -> Result<(), Error> {
+  let token = app.http_auth_token.as_deref();
+  let secret = app.http_auth_hmac_secret.as_deref();
+
+  let bearer = req.header("Authorization")
+                  .and_then(|h| h.strip_prefix("Bearer "));
+  let signature = req.header(SIGNATURE_HEADER);
+
+  match (token, bearer, secret, signature) {
+    | (None, _, None, _) => Err(Error::NotConfigured),
+    | (Some(token), Some(bearer), _, _) => {
+      if verify_token(token, bearer) {
+        Ok(())
+      } else {
+        Err(Error::BadToken)
+      }
+    },
+    | (_, _, Some(secret), Some(signature)) => {
+      verify_signed(secret, signature, req, now)
+    },
+    | _ => Err(Error::Missing),
+  }
+}
+
+fn verify_signed(secret: &str,
+                 signature: &str,
+                 req: &HttpRequest,
+                 now: DateTime<Utc>)
+                 -> Result<(), Error> {
+  let timestamp = req.header(TIMESTAMP_HEADER).ok_or(Error::BadTimestamp)?;
+  let sent_at = timestamp.trim()
+                         .parse::<i64>()
+                         .map_err(|_| Error::BadTimestamp)?;
+
+  if (now.timestamp() - sent_at).abs() > MAX_SKEW_SECS {
+    return Err(Error::Expired);
+  }
+
+  let mut message = format!("{}.{}.{}.{}.",
+                            timestamp.trim(),
+                            req.method,
+                            req.path,
+                            canonical_query(req)).into_bytes();
+  message.extend_from_slice(req.body_bytes());
+
+  if verify_hmac_sha256(secret.as_bytes(), &message, signature) {
+    Ok(())
+  } else {
+    Err(Error::BadSignature)
+  }
+}
+
+/// The query parameters sorted by name and form-urlencoded, so the
+/// signature covers them however the caller ordered them
+fn canonical_query(req: &HttpRequest) -> String {
+  let mut params = req.query.iter().flatten().collect::<Vec<_>>();
+  params.sort();
+
+  url::form_urlencoded::Serializer::new(String::new()).extend_pairs(params)
+                                                      .finish()
+}
+
+/// Check a `sha256=<hex>` (or bare hex) signature of `message`
+pub fn verify_hmac_sha256(secret: &[u8],
+                          message: &[u8],
+                          signature: &str)
+                          -> bool {
+  let sig_hex = signature.trim();
+  let sig_hex = sig_hex.strip_prefix("sha256=").unwrap_or(sig_hex);
+
+  let sig = match hex::decode(sig_hex) {
+    | Ok(sig) => sig,
+    | Err(_) => return false,
+  };
+
+  let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+                .expect("HMAC should accept keys of any length");
+  mac.update(message);
+  mac.verify_slice(&sig).is_ok()
+}
+
+/// Check a token presented by a caller against the one expected
+pub fn verify_token(expected: &str, given: &str) -> bool {
+  ct_eq(expected.as_bytes(), given.trim().as_bytes())
+}
+
+/// Compare two byte strings without short-circuiting on the first difference
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len()
+  && a.iter().zip(b).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lamb::{Format, HttpMethod};
+
+  fn sign(secret: &str, message: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(message.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+  }
+
+  #[test]
+  pub fn verify_hmac_sha256_should_accept_matching_signature() {
+    // ARRANGE
+    let sig = sign("secret", "1618000000.POST./execute.");
+
+    // ACT
+    let ok = verify_hmac_sha256(b"secret", b"1618000000.POST./execute.", &sig);
+    let tampered =
+      verify_hmac_sha256(b"secret", b"1618000000.POST./notify.", &sig);
+
+    // ASSERT
+    assert!(ok);
+    assert!(!tampered);
+  }
+
+  #[test]
+  pub fn verify_signed_should_cover_the_query() {
+    // ARRANGE
+    let now = Utc::now();
+    let req = |query: &[(&str, &str)]| {
+      let query = query.iter()
+                       .map(|(k, v)| (k.to_string(), v.to_string()))
+                       .collect();
+      let headers = vec![(TIMESTAMP_HEADER.to_string(),
+                          now.timestamp().to_string())].into_iter()
+                                                       .collect();
+      HttpRequest { path: "/events".into(),
+                    method: HttpMethod::Get,
+                    headers: Some(headers),
+                    query: Some(query),
+                    body: None,
+                    base64: false,
+                    format: Format::HttpV2 }
+    };
+    let sig = sign("secret",
+                   &format!("{}.GET./events.from=2021-04-08&tz=UTC.",
+                            now.timestamp()));
+
+    // ACT
+    let signed = req(&[("tz", "UTC"), ("from", "2021-04-08")]);
+    let changed = req(&[("tz", "UTC"), ("from", "2021-01-01")]);
+
+    // ASSERT
+    assert!(verify_signed("secret", &sig, &signed, now).is_ok());
+    assert!(matches!(verify_signed("secret", &sig, &changed, now),
+                     Err(Error::BadSignature)));
+  }
+
+  #[test]
+  pub fn ct_eq_should_compare_bytes() {
+    assert!(ct_eq(b"token", b"token"));
+    assert!(!ct_eq(b"token", b"tokem"));
+    assert!(!ct_eq(b"token", b"token2"));
+  }
+}
@@ -0,0 +1,101 @@
+//! Cross-origin resource sharing, so browsers on other origins
+//! can call the API.
+//!
+//! Configured with `App::cors_allowed_*`. No origins are allowed by default,
+//! and an origin of `*` allows any origin. When no methods are configured,
+//! the methods routed for the requested path are allowed.
+
+use super::{auth, idempotency, REQUEST_ID_HEADER};
+use crate::{app::App,
+            lamb::{HttpMethod, HttpRequest, HttpResponse}};
+
+const DEFAULT_ALLOWED_HEADERS: &[&str] = &["Authorization",
+                                           "Content-Type",
+                                           auth::TIMESTAMP_HEADER,
+                                           auth::SIGNATURE_HEADER,
+                                           idempotency::HEADER];
+
+const MAX_AGE_SECS: u32 = 10 * 60;
+
+#[derive(Debug, Clone, Default)]
+pub struct Cors {
+  origins: Vec<String>,
+  methods: Vec<String>,
+  headers: Vec<String>,
+}
+
+impl Cors {
+  pub fn new(app: &App) -> Self {
+    Self { origins: app.cors_allowed_origins.clone(),
+           methods: app.cors_allowed_methods.clone(),
+           headers: app.cors_allowed_headers.clone() }
+  }
+
+  /// Is this an `OPTIONS` request sent by a browser ahead of
+  /// a cross-origin request?
+  pub fn is_preflight(req: &HttpRequest) -> bool {
+    req.method == HttpMethod::Options
+    && req.header("Access-Control-Request-Method").is_some()
+  }
+
+  /// The `Access-Control-Allow-Origin` value for a request from `origin`,
+  /// if that origin is allowed
+  fn allow_origin(&self, origin: &str) -> Option<String> {
+    if self.origins.iter().any(|o| o == "*") {
+      Some("*".into())
+    } else {
+      self.origins
+          .iter()
+          .find(|o| o.trim_end_matches('/') == origin)
+          .cloned()
+    }
+  }
+
+  /// Answer a preflight request for a path routed to `route_methods`
+  pub fn preflight(&self,
+                   req: &HttpRequest,
+                   route_methods: &[HttpMethod])
+                   -> HttpResponse {
+    let methods = if self.methods.is_empty() {
+      route_methods.iter().map(ToString::to_string).collect()
+    } else {
+      self.methods.clone()
+    };
+
+    let headers = if self.headers.is_empty() {
+      DEFAULT_ALLOWED_HEADERS.iter()
+                             .map(ToString::to_string)
+                             .collect()
+    } else {
+      self.headers.clone()
+    };
+
+    let resp = HttpResponse::new().status(204);
+
+    match req.header("Origin") {
+      | Some(origin) if self.allow_origin(origin).is_some() => {
+        self.apply(origin, resp)
+            .header("Access-Control-Allow-Methods", methods.join(", "))
+            .header("Access-Control-Allow-Headers", headers.join(", "))
+            .header("Access-Control-Max-Age", MAX_AGE_SECS)
+      },
+      | Some(origin) => {
+        log::warn!("Rejecting preflight from disallowed origin {}", origin);
+        resp
+      },
+      | None => resp,
+    }
+  }
+
+  /// Add CORS headers to a response for a request from `origin`
+  pub fn apply(&self, origin: &str, resp: HttpResponse) -> HttpResponse {
+    match self.allow_origin(origin) {
+      | Some(allow) => {
+        resp.header("Access-Control-Allow-Origin", allow)
+            .header("Access-Control-Expose-Headers", REQUEST_ID_HEADER)
+            .header("Vary", "Origin")
+      },
+      | None => resp,
+    }
+  }
+}
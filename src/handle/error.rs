@@ -0,0 +1,211 @@
+//! Errors that HTTP handlers respond with, and the envelope they're sent in:
+//!
+//! ```json
+//! {"success": false, "error": {"code": "not_found", "message": "..."}}
+//! ```
+//!
+//! Handlers can return an [`HttpError`] directly, and other errors are
+//! mapped to one by [`HttpError::from_any`]. Only the message of the mapped
+//! error is sent back; details of internal errors are logged instead.
+
+use schemars::JsonSchema;
+use serde::Serialize as Ser;
+use thiserror::Error as DeriveError;
+
+use super::auth;
+use crate::{app, integrate::ad, lamb::HttpResponse, notify};
+
+/// The body of error responses
+#[derive(Debug, Ser, JsonSchema)]
+pub struct Envelope {
+  error: Detail,
+  success: bool,
+}
+
+#[derive(Debug, Ser, JsonSchema)]
+pub struct Detail {
+  /// See [`HttpError::code`]
+  code: String,
+  message: String,
+}
+
+#[derive(Clone, Debug, DeriveError)]
+pub enum HttpError {
+  #[error("{0}")]
+  BadRequest(String),
+
+  #[error("{0}")]
+  Unauthorized(String),
+
+  #[error("{0}")]
+  NotFound(String),
+
+  #[error("{0}")]
+  MethodNotAllowed(String),
+
+  #[error("{0}")]
+  Conflict(String),
+
+  #[error("{0}")]
+  BadGateway(String),
+
+  #[error("{0}")]
+  Unavailable(String),
+
+  #[error("Internal error")]
+  Internal,
+}
+
+impl HttpError {
+  pub fn status(&self) -> i32 {
+    match self {
+      | Self::BadRequest(_) => 400,
+      | Self::Unauthorized(_) => 401,
+      | Self::NotFound(_) => 404,
+      | Self::MethodNotAllowed(_) => 405,
+      | Self::Conflict(_) => 409,
+      | Self::Internal => 500,
+      | Self::BadGateway(_) => 502,
+      | Self::Unavailable(_) => 503,
+    }
+  }
+
+  /// Stable, machine-readable name of the error
+  pub fn code(&self) -> &'static str {
+    match self {
+      | Self::BadRequest(_) => "bad_request",
+      | Self::Unauthorized(_) => "unauthorized",
+      | Self::NotFound(_) => "not_found",
+      | Self::MethodNotAllowed(_) => "method_not_allowed",
+      | Self::Conflict(_) => "conflict",
+      | Self::Internal => "internal",
+      | Self::BadGateway(_) => "bad_gateway",
+      | Self::Unavailable(_) => "unavailable",
+    }
+  }
+
+  pub fn response(&self) -> HttpResponse {
+    let body = Envelope { success: false,
+                          error: Detail { code: self.code().into(),
+                                          message: self.to_string() } };
+
+    HttpResponse::new().status(self.status())
+                       .body_json(body)
+                       .expect("error should serialize")
+  }
+
+  /// Map any error to the response it should produce
+  pub fn from_any(e: &(dyn std::error::Error + 'static)) -> Self {
+    if let Some(e) = e.downcast_ref::<HttpError>() {
+      e.clone()
+    } else if let Some(e) = e.downcast_ref::<app::Error>() {
+      e.into()
+    } else if let Some(e) = e.downcast_ref::<ad::Error>() {
+      e.into()
+    } else if let Some(e) = e.downcast_ref::<notify::Error>() {
+      e.into()
+    } else if let Some(e) = e.downcast_ref::<auth::Error>() {
+      e.into()
+    } else if let Some(e) = e.downcast_ref::<serde_json::Error>() {
+      Self::BadRequest(format!("Invalid JSON: {}", e))
+    } else if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+      upstream(e)
+    } else {
+      Self::Internal
+    }
+  }
+
+  /// The most severe of several errors
+  fn worst(errs: impl Iterator<Item = Self>) -> Self {
+    errs.max_by_key(Self::status).unwrap_or(Self::Internal)
+  }
+}
+
+fn upstream(e: &reqwest::Error) -> HttpError {
+  let url = e.url()
+             .map(|u| u.host_str().unwrap_or_default().to_string());
+
+  match url {
+    | Some(host) => {
+      HttpError::BadGateway(format!("Request to {} failed", host))
+    },
+    | None => HttpError::BadGateway("Upstream request failed".into()),
+  }
+}
+
+impl From<&app::Error> for HttpError {
+  fn from(e: &app::Error) -> Self {
+    match e {
+      | app::Error::EnvVarsMissing(_) => {
+        Self::Unavailable("App is missing configuration".into())
+      },
+      | app::Error::Reqwest(e) => upstream(e),
+      | app::Error::Other(_) => Self::Internal,
+      | app::Error::Any(e) => Self::from_any(e.as_ref()),
+      | app::Error::Many(errs) => {
+        Self::worst(errs.iter().map(|e| Self::from_any(e.as_ref())))
+      },
+    }
+  }
+}
+
+impl From<&ad::Error> for HttpError {
+  fn from(e: &ad::Error) -> Self {
+    match e {
+      | ad::Error::Reqwest(e) => upstream(e),
+      | ad::Error::Json(_) | ad::Error::Other(_) => {
+        Self::BadGateway("Integrate AD returned an error".into())
+      },
+      | ad::Error::CodePending => {
+        Self::Unavailable("Waiting for Integrate AD code to be entered".into())
+      },
+      | ad::Error::CodeDeclined
+      | ad::Error::CodeBad
+      | ad::Error::CodeExpired => {
+        Self::Unavailable("Integrate AD sign-in failed, start a new one".into())
+      },
+    }
+  }
+}
+
+impl From<&notify::Error> for HttpError {
+  fn from(e: &notify::Error) -> Self {
+    match e {
+      | notify::Error::Reqwest(e) => upstream(e),
+      | notify::Error::Pushbullet(_) | notify::Error::Json(_) => {
+        Self::BadGateway("Notifier returned an error".into())
+      },
+      | notify::Error::Many(errs) => Self::worst(errs.iter().map(Self::from)),
+    }
+  }
+}
+
+impl From<&auth::Error> for HttpError {
+  fn from(e: &auth::Error) -> Self {
+    Self::Unauthorized(e.to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::AnyError;
+
+  #[test]
+  pub fn from_any_should_see_through_wrapped_errors() {
+    // ARRANGE
+    let pending: AnyError =
+      Box::new(app::Error::Any(Box::new(ad::Error::CodePending)));
+    let other: AnyError = Box::new(app::Error::Other("secret detail".into()));
+
+    // ACT
+    let pending = HttpError::from_any(pending.as_ref());
+    let other = HttpError::from_any(other.as_ref());
+
+    // ASSERT
+    assert_eq!(pending.status(), 503);
+    assert_eq!(other.status(), 500);
+    assert_eq!(other.response().body.unwrap(),
+               r#"{"error":{"code":"internal","message":"Internal error"},"success":false}"#);
+  }
+}
@@ -0,0 +1,135 @@
+//! `GET /events`, the merged events of every configured calendar.
+//!
+//! Query parameters:
+//! - `from`, `to`: RFC 3339 timestamps, or `YYYY-MM-DD` dates meaning
+//!   the start of that day in `App::timezone`. `to` is exclusive.
+//!   `from` defaults to the start of today, and `to` to the end of the
+//!   day `from` is in.
+//! - `cat`: only events in a category, e.g. `work`, `personal` or
+//!   `personal:chore` (see [`CatFilter`]).
+//!
+//! Refreshes the Integrate AD token first, but responds 503 when not signed
+//! in rather than starting a sign-in (see [`super::ad::require_authed`]).
+
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::Serialize as Ser;
+
+use super::{ad,
+            openapi::{self, Doc},
+            router,
+            HttpError,
+            State};
+use crate::{app::state::Ext,
+            calendar::{CatFilter, Event},
+            lamb::{HttpRequest, HttpResponse},
+            prelude::*,
+            time::{Clock, Days, SystemClock},
+            AnyError};
+
+#[derive(Debug)]
+struct Query {
+  from: DateTime<Utc>,
+  to: DateTime<Utc>,
+  cat: Option<CatFilter>,
+}
+
+impl Query {
+  fn parse(req: &HttpRequest,
+           days: Days,
+           clock: &impl Clock)
+           -> Result<Self, String> {
+    let from = match req.query_param("from") {
+      | Some(from) => parse_time(from, days)?,
+      | None => days.day(days.today(clock)).start,
+    };
+
+    let to = match req.query_param("to") {
+      | Some(to) => parse_time(to, days)?,
+      | None => days.day(days.date(from)).end,
+    };
+
+    if to <= from {
+      return Err(format!("to ({}) must be after from ({})", to, from));
+    }
+
+    let cat = req.query_param("cat")
+                 .map(str::parse::<CatFilter>)
+                 .transpose()
+                 .map_err(|e| e.to_string())?;
+
+    Ok(Self { from, to, cat })
+  }
+}
+
+fn parse_time(s: &str, days: Days) -> Result<DateTime<Utc>, String> {
+  let as_date =
+    || NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| days.start(d));
+
+  DateTime::parse_from_rfc3339(s)
+    .map(|dt| dt.with_timezone(&Utc))
+    .or_else(|_| as_date())
+    .map_err(|_| format!("{:?} is not a date or RFC 3339 timestamp", s))
+}
+
+pub const DOC: Doc =
+  Doc::new("Events of every calendar, by start time")
+    .query(&[("from", "RFC 3339 timestamp or YYYY-MM-DD, defaults to today"),
+             ("to", "Exclusive end, defaults to the end of from's day"),
+             ("cat", "Only events in a category, e.g. work or personal:chore")])
+    .response(openapi::schema::<Response>);
+
+#[derive(Debug, Ser, JsonSchema)]
+struct Response {
+  success: bool,
+  events: Vec<Event>,
+}
+
+pub fn get<S: State>(state: &S,
+                     req: router::Req)
+                     -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    ad::require_authed(state).await?;
+    let app = state.read().norm()?;
+
+    let days = Days::new(app.timezone);
+    let query = match Query::parse(&req.http, days, &SystemClock) {
+      | Ok(query) => query,
+      | Err(e) => return Err(HttpError::BadRequest(e).into()),
+    };
+
+    let mut events = state.get_events(query.from, query.to).await?;
+
+    if let Some(cat) = &query.cat {
+      events.retain(|e| cat.matches(&e.cat));
+    }
+
+    events.sort_by_key(|e| e.time_start);
+
+    HttpResponse::new().body_json(Response { success: true,
+                                             events })
+                       .norm()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::*;
+
+  #[test]
+  pub fn parse_time_should_accept_dates_and_timestamps() {
+    // ACT
+    let days = Days::new(chrono_tz::America::Denver);
+    let date = parse_time("2021-04-08", days);
+    let stamp = parse_time("2021-04-08T08:30:00-06:00", days);
+    let junk = parse_time("tomorrow", days);
+
+    // ASSERT
+    assert_eq!(date, Ok(Utc.ymd(2021, 4, 8).and_hms(6, 0, 0)));
+    assert_eq!(stamp, Ok(Utc.ymd(2021, 4, 8).and_hms(14, 30, 0)));
+    assert!(junk.is_err());
+  }
+}
@@ -0,0 +1,349 @@
+//! `POST /webhooks/github`, turning GitHub webhook deliveries into
+//! notifications.
+//!
+//! Deliveries must be signed with `App::github_webhook_secret`
+//! (`X-Hub-Signature-256`), so the route skips inbound authentication.
+//!
+//! Forwarded kinds of events, all of them unless `App::github_events`
+//! names a subset:
+//! - `review_requested`: a review is requested from `App::github_username`
+//!   (or anyone, if unset)
+//! - `ci_failure`: a workflow run or check run fails on one of
+//!   `App::github_branches` (or any branch, if none are configured)
+//! - `mention`: `@App::github_username` appears in a new issue, pull
+//!   request, review or comment
+
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::Serialize as Ser;
+use serde_json::{json, Value};
+
+use super::{auth,
+            openapi::{self, Doc},
+            router,
+            HttpError,
+            State};
+use crate::{app::{state::Ext, App},
+            lamb::HttpResponse,
+            prelude::*,
+            AnyError};
+
+pub const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+pub const EVENT_HEADER: &str = "X-GitHub-Event";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+  ReviewRequested,
+  CiFailure,
+  Mention,
+}
+
+impl Kind {
+  fn name(&self) -> &'static str {
+    match self {
+      | Self::ReviewRequested => "review_requested",
+      | Self::CiFailure => "ci_failure",
+      | Self::Mention => "mention",
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+struct Notification {
+  kind: Kind,
+  title: String,
+  body: String,
+}
+
+struct Config<'a> {
+  events: &'a [String],
+  branches: &'a [String],
+  username: Option<&'a str>,
+}
+
+impl<'a> Config<'a> {
+  fn new(app: &'a App) -> Self {
+    Self { events: &app.github_events,
+           branches: &app.github_branches,
+           username: app.github_username.as_deref() }
+  }
+
+  fn forwards(&self, kind: Kind) -> bool {
+    self.events.is_empty() || self.events.iter().any(|e| e == kind.name())
+  }
+
+  fn watches_branch(&self, branch: &str) -> bool {
+    self.branches.is_empty() || self.branches.iter().any(|b| b == branch)
+  }
+
+  fn is_me(&self, login: &str) -> bool {
+    self.username
+        .map(|me| me.eq_ignore_ascii_case(login))
+        .unwrap_or(true)
+  }
+
+  /// Whether `text` has an `@login` mention of the user, and not just
+  /// of a login that starts with theirs
+  fn mentions_me(&self, text: &str) -> bool {
+    let me = match self.username {
+      | Some(me) => format!("@{}", me.to_lowercase()),
+      | None => return false,
+    };
+    let continues_login = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+
+    let text = text.to_lowercase();
+    text.match_indices(&me)
+        .any(|(ix, _)| !text[ix + me.len()..].starts_with(continues_login))
+  }
+}
+
+fn str_at<'v>(payload: &'v Value, pointer: &str) -> &'v str {
+  payload.pointer(pointer)
+         .and_then(Value::as_str)
+         .unwrap_or_default()
+}
+
+/// The notification to send for a delivery of `event`, if any
+fn notification(event: &str,
+                payload: &Value,
+                cfg: &Config)
+                -> Option<Notification> {
+  let repo = str_at(payload, "/repository/full_name");
+  let action = str_at(payload, "/action");
+
+  let (kind, title, body) = match (event, action) {
+    | ("pull_request", "review_requested")
+      if cfg.is_me(str_at(payload, "/requested_reviewer/login")) =>
+    {
+      (Kind::ReviewRequested,
+       format!("Review requested: {}#{}",
+               repo,
+               payload.pointer("/number").cloned().unwrap_or_default()),
+       format!("{}\n{}",
+               str_at(payload, "/pull_request/title"),
+               str_at(payload, "/pull_request/html_url")))
+    },
+    | ("workflow_run", "completed") | ("check_run", "completed") => {
+      let (run, branch) = if event == "workflow_run" {
+        ("/workflow_run", str_at(payload, "/workflow_run/head_branch"))
+      } else {
+        ("/check_run", str_at(payload, "/check_run/check_suite/head_branch"))
+      };
+
+      let conclusion = str_at(payload, &format!("{}/conclusion", run));
+      let failed =
+        matches!(conclusion, "failure" | "timed_out" | "startup_failure");
+
+      if !failed || !cfg.watches_branch(branch) {
+        return None;
+      }
+
+      (Kind::CiFailure,
+       format!("CI failed: {} ({})", repo, branch),
+       format!("{} {}\n{}",
+               str_at(payload, &format!("{}/name", run)),
+               conclusion,
+               str_at(payload, &format!("{}/html_url", run))))
+    },
+    | ("issue_comment", "created")
+    | ("pull_request_review_comment", "created")
+    | ("pull_request_review", "submitted")
+    | ("issues", "opened")
+    | ("pull_request", "opened") => {
+      let subject = match event {
+        | "issue_comment" => "/comment",
+        | "pull_request_review_comment" => "/comment",
+        | "pull_request_review" => "/review",
+        | "issues" => "/issue",
+        | _ => "/pull_request",
+      };
+
+      let text = str_at(payload, &format!("{}/body", subject));
+      if !cfg.mentions_me(text) {
+        return None;
+      }
+
+      (Kind::Mention,
+       format!("Mentioned by {} in {}",
+               str_at(payload, "/sender/login"),
+               repo),
+       format!("{}\n{}",
+               text,
+               str_at(payload, &format!("{}/html_url", subject))))
+    },
+    | _ => return None,
+  };
+
+  Some(Notification { kind, title, body }).filter(|n| cfg.forwards(n.kind))
+}
+
+pub const DOC: Doc =
+  Doc::new("Receive a GitHub webhook delivery, signed with X-Hub-Signature-256")
+    .request(|| json!({"description": "Any GitHub webhook payload"}))
+    .response(openapi::schema::<Forwarded>);
+
+/// `{"success": true, "forwarded": ...}`
+#[derive(Debug, Ser, JsonSchema)]
+pub struct Forwarded {
+  pub success: bool,
+
+  /// Whether a notification was sent
+  pub forwarded: bool,
+}
+
+pub fn post<S: State>(state: &S,
+                      req: router::Req)
+                      -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    let app = state.read().norm()?;
+    let req = req.http;
+
+    let secret = match app.github_webhook_secret.as_deref() {
+      | Some(secret) => secret,
+      | None => {
+        let msg = "GitHub webhooks are not configured";
+        return Err(HttpError::Unauthorized(msg.into()).into());
+      },
+    };
+
+    let signature = req.header(SIGNATURE_HEADER).unwrap_or_default();
+    if !auth::verify_hmac_sha256(secret.as_bytes(), req.body_bytes(), signature)
+    {
+      log::warn!("Rejecting GitHub webhook with invalid signature");
+      let msg = "Webhook signature is invalid";
+      return Err(HttpError::Unauthorized(msg.into()).into());
+    }
+
+    let event = req.header(EVENT_HEADER).unwrap_or_default();
+
+    // webhooks can be configured to send `payload=<json>` as a form
+    let payload = if req.is_form() {
+      req.body_form()
+         .get("payload")
+         .map(|p| serde_json::from_str::<Value>(p))
+         .unwrap_or_else(|| Ok(Value::Null))
+    } else {
+      req.body_json::<Value>()
+    };
+
+    let payload = match payload {
+      | Ok(payload) => payload,
+      | Err(e) => {
+        let msg = format!("Invalid payload: {}", e);
+        return Err(HttpError::BadRequest(msg).into());
+      },
+    };
+
+    let forwarded = match notification(event, &payload, &Config::new(app)) {
+      | Some(n) => {
+        log::info!("Forwarding GitHub {} as {}", event, n.kind.name());
+        state.notify(&n.title, &n.body).await?;
+        true
+      },
+      | None => {
+        log::info!("Ignoring GitHub {} ({})",
+                   event,
+                   str_at(&payload, "/action"));
+        false
+      },
+    };
+
+    HttpResponse::new().body_json(Forwarded { success: true,
+                                              forwarded })
+                       .norm()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config<'a>(events: &'a [String], branches: &'a [String]) -> Config<'a> {
+    Config { events,
+             branches,
+             username: Some("octocat") }
+  }
+
+  #[test]
+  pub fn review_request_for_me_should_notify() {
+    // ARRANGE
+    let payload = json!({
+      "action": "review_requested",
+      "number": 42,
+      "requested_reviewer": {"login": "OctoCat"},
+      "pull_request": {"title": "Add hooks", "html_url": "https://github.com/o/r/pull/42"},
+      "repository": {"full_name": "o/r"},
+    });
+
+    // ACT
+    let n = notification("pull_request", &payload, &config(&[], &[]));
+
+    // ASSERT
+    assert_eq!(n,
+               Some(Notification { kind: Kind::ReviewRequested,
+                                   title: "Review requested: o/r#42".into(),
+                                   body: "Add hooks\nhttps://github.com/o/r/pull/42".into() }));
+  }
+
+  #[test]
+  pub fn ci_failure_should_respect_branches_and_events() {
+    // ARRANGE
+    let payload = |branch: &str| {
+      json!({
+        "action": "completed",
+        "workflow_run": {"name": "CI", "conclusion": "failure", "head_branch": branch, "html_url": "u"},
+        "repository": {"full_name": "o/r"},
+      })
+    };
+    let main = vec!["main".to_string()];
+    let mentions_only = vec!["mention".to_string()];
+
+    // ACT
+    let on_main =
+      notification("workflow_run", &payload("main"), &config(&[], &main));
+    let on_feature =
+      notification("workflow_run", &payload("feature"), &config(&[], &main));
+    let not_forwarded = notification("workflow_run",
+                                     &payload("main"),
+                                     &config(&mentions_only, &main));
+
+    // ASSERT
+    assert!(matches!(on_main,
+                     Some(Notification { kind: Kind::CiFailure,
+                                         .. })));
+    assert_eq!(on_feature, None);
+    assert_eq!(not_forwarded, None);
+  }
+
+  #[test]
+  pub fn comment_mentioning_me_should_notify() {
+    // ARRANGE
+    let payload = |body: &str| {
+      json!({
+        "action": "created",
+        "comment": {"body": body, "html_url": "u"},
+        "sender": {"login": "hubot"},
+        "repository": {"full_name": "o/r"},
+      })
+    };
+
+    // ACT
+    let mention =
+      notification("issue_comment", &payload("cc @octocat"), &config(&[], &[]));
+    let capitalized =
+      notification("issue_comment", &payload("cc @OctoCat"), &config(&[], &[]));
+    let no_mention =
+      notification("issue_comment", &payload("lgtm"), &config(&[], &[]));
+    let longer_login = notification("issue_comment",
+                                    &payload("cc @octocatfan, @octocat-bot"),
+                                    &config(&[], &[]));
+
+    // ASSERT
+    assert!(matches!(mention,
+                     Some(Notification { kind: Kind::Mention,
+                                         .. })));
+    assert!(capitalized.is_some());
+    assert_eq!(no_mention, None);
+    assert_eq!(longer_login, None);
+  }
+}
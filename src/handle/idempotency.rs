@@ -0,0 +1,140 @@
+//! `Idempotency-Key` support for mutating requests, so a retried request
+//! isn't acted on twice.
+//!
+//! The first response to a request carrying a key is stored in app state
+//! for `App::idempotency_ttl_secs` (a day by default) and replayed for
+//! repeats of that request, marked with `Idempotent-Replayed: true`.
+//! Reusing a key for a different request is a conflict. Server errors
+//! aren't stored, so those requests can be retried.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize as De, Serialize as Ser};
+use sha2::{Digest, Sha256};
+
+use super::{HttpError, State};
+use crate::{lamb::{HttpRequest, HttpResponse},
+            prelude::*,
+            AnyError};
+
+pub const HEADER: &str = "Idempotency-Key";
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const DEFAULT_TTL_SECS: u32 = 24 * 60 * 60;
+
+/// A response stored for an idempotency key
+#[derive(Clone, Debug, Ser, De)]
+pub struct Record {
+  /// Hash of the request the response is for
+  fingerprint: String,
+  response: HttpResponse,
+  expires: DateTime<Utc>,
+}
+
+pub enum Lookup {
+  /// The key hasn't been seen (or has expired)
+  Miss,
+  Replay(HttpResponse),
+  Conflict(HttpError),
+}
+
+/// Hash of what makes a request the same request
+pub fn fingerprint(req: &HttpRequest) -> String {
+  let mut hash = Sha256::new();
+  hash.update(req.method.to_string());
+  hash.update(b"\n");
+  hash.update(&req.path);
+  hash.update(b"\n");
+  hash.update(req.body_bytes());
+  hex::encode(hash.finalize())
+}
+
+/// Look up a key among `App::idempotency`'s records
+pub fn lookup(records: &HashMap<String, Record>,
+              key: &str,
+              fingerprint: &str,
+              now: DateTime<Utc>)
+              -> Lookup {
+  match records.get(key) {
+    | Some(rec) if rec.expires > now => {
+      if rec.fingerprint == fingerprint {
+        log::info!("Replaying response for idempotency key {}", key);
+        Lookup::Replay(rec.response.clone().header(REPLAYED_HEADER, true))
+      } else {
+        let msg = format!("{} {} was already used for a different request",
+                          HEADER, key);
+        Lookup::Conflict(HttpError::Conflict(msg))
+      }
+    },
+    | _ => Lookup::Miss,
+  }
+}
+
+/// Store the response to a request, forgetting any expired ones
+pub fn store(state: &impl State,
+             key: &str,
+             fingerprint: String,
+             response: &HttpResponse,
+             now: DateTime<Utc>)
+             -> Result<(), AnyError> {
+  if response.status >= 500 {
+    return Ok(());
+  }
+
+  state.modify(|mut app| {
+         let ttl = app.idempotency_ttl_secs.unwrap_or(DEFAULT_TTL_SECS);
+         let expires = now + Duration::seconds(i64::from(ttl));
+
+         app.idempotency.retain(|_, rec| rec.expires > now);
+         app.idempotency.insert(key.to_string(),
+                                Record { fingerprint,
+                                         response: response.clone(),
+                                         expires });
+         Ok(app)
+       })
+       .norm()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lamb::{Body, Format, HttpMethod};
+
+  fn req(body: &str) -> HttpRequest {
+    HttpRequest { path: "/execute".into(),
+                  method: HttpMethod::Post,
+                  headers: None,
+                  query: None,
+                  body: Some(Body(body.as_bytes().to_vec())),
+                  base64: false,
+                  format: Format::HttpV2 }
+  }
+
+  #[test]
+  pub fn lookup_should_replay_only_the_same_unexpired_request() {
+    // ARRANGE
+    let now = Utc::now();
+    let mut records = HashMap::new();
+    let record = Record { fingerprint: fingerprint(&req("{}")),
+                          response: HttpResponse::new().body("done"),
+                          expires: now + Duration::minutes(1) };
+    records.insert("a".to_string(), record);
+
+    // ACT
+    let same = lookup(&records, "a", &fingerprint(&req("{}")), now);
+    let different = lookup(&records, "a", &fingerprint(&req("{\"x\":1}")), now);
+    let expired = lookup(&records,
+                         "a",
+                         &fingerprint(&req("{}")),
+                         now + Duration::minutes(2));
+
+    // ASSERT
+    match same {
+      | Lookup::Replay(r) => assert_eq!(r.body.as_deref(), Some("done")),
+      | _ => panic!("should replay"),
+    }
+    assert!(matches!(different, Lookup::Conflict(HttpError::Conflict(_))));
+    assert!(matches!(expired, Lookup::Miss));
+  }
+}
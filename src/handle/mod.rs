@@ -0,0 +1,385 @@
+use chrono::Utc;
+pub use error::HttpError;
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::Serialize as Ser;
+use serde_json::Value;
+
+use crate::{app, app::state, lamb, prelude::*, AnyError};
+
+pub mod ad;
+pub mod auth;
+pub mod cors;
+pub mod error;
+pub mod events;
+pub mod github;
+pub mod idempotency;
+pub mod notify;
+pub mod openapi;
+pub mod queue;
+pub mod router;
+pub mod summary;
+pub mod webhook;
+
+/// Header carrying the AWS request ID of the invocation that responded
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// App state as seen by handlers
+pub trait State: state::Read + state::Modify + Sync {}
+impl<T: state::Read + state::Modify + Sync> State for T {}
+
+pub fn noop() -> Result<Value, crate::AnyError> {
+  log::info!("noop called, exiting app");
+  serde_json::to_value(()).norm()
+}
+
+/// Run the jobs that are due, or only the job named `force` regardless of
+/// its schedule.
+pub async fn jobs<S: State>(state: &S,
+                            force: Option<&str>,
+                            ctx: &lamb::Context)
+                            -> Result<Vec<crate::jobs::Report>, AnyError> {
+  let registry = crate::jobs::registry::<S>();
+  let now = Utc::now();
+
+  match force {
+    | Some(name) => {
+      let job =
+        registry.get(name).ok_or_else(|| {
+                             app::Error::Other(format!("No job named {}", name))
+                           })?;
+      Ok(vec![registry.run(state, job, now).await])
+    },
+    | None => registry.run_due(state, now, ctx.deadline).await,
+  }
+}
+
+/// Handle a `RunJobs` tick
+pub async fn run_jobs<S: State>(state: &S,
+                                ctx: &lamb::Context)
+                                -> Result<Value, AnyError> {
+  let failed = jobs(state, None, ctx).await?
+                                     .into_iter()
+                                     .filter(|r| !r.ok)
+                                     .map(|r| {
+                                       format!("{}: {}",
+                                               r.job,
+                                               r.error.unwrap_or_default())
+                                     })
+                                     .collect::<Vec<_>>();
+
+  if failed.is_empty() {
+    noop()
+  } else {
+    Err(app::Error::Other(format!("Jobs failed:\n{}", failed.join("\n")))).norm()
+  }
+}
+
+/// Run the handler for a kind of schedule
+pub async fn schedule<S: State>(state: &S,
+                                kind: lamb::ScheduleKind,
+                                ctx: &lamb::Context)
+                                -> Result<Value, AnyError> {
+  use lamb::{ScheduleKind::*, SummaryParams};
+
+  log::info!("Handling schedule {:?}", kind);
+
+  match kind {
+    | KeepWarm => keep_warm(state).await,
+    | RunJobs => run_jobs(state, ctx).await,
+    | SummaryToday => summary::send(state, SummaryParams::today()).await,
+    | SummaryTomorrow => summary::send(state, SummaryParams::tomorrow()).await,
+    | SummaryWeek => summary::send(state, SummaryParams::week()).await,
+    | Summary(params) => summary::send(state, params).await,
+  }
+}
+
+/// Acknowledge a schedule this version doesn't understand, so that
+/// it isn't retried
+pub fn unknown_schedule(kind: Value) -> Result<Value, AnyError> {
+  log::warn!("Ignoring unknown schedule kind {}", kind);
+  noop()
+}
+
+/// Initialize app state and open connections to the APIs we talk to,
+/// so the next real invocation doesn't pay for either
+pub async fn keep_warm<S: State>(state: &S) -> Result<Value, AnyError> {
+  let app = state.read().norm()?;
+
+  let urls = vec![&app.pushbullet_base_url,
+                  &app.integrate_ad_login_base_url,
+                  &app.ms_graph_base_url];
+
+  futures::future::join_all(urls.into_iter()
+                                .filter(|url| !url.is_empty())
+                                .map(|url| async move {
+                                  app.reqw
+                                     .head(url.as_str())
+                                     .send()
+                                     .await
+                                     .tap(|r| {
+                                       log::info!("> warmed {} ({})",
+                                                  url,
+                                                  r.status())
+                                     })
+                                     .tap_err(|e| {
+                                       log::warn!("> failed to warm {}: {}",
+                                                  url,
+                                                  e)
+                                     })
+                                })).await;
+
+  noop()
+}
+
+/// Handle a native EventBridge scheduled event by looking up
+/// the kind configured for its rule in `App::schedule_rules`
+pub async fn scheduled_event(state: &impl State,
+                             event: lamb::ScheduledEvent,
+                             ctx: &lamb::Context)
+                             -> Result<Value, crate::AnyError> {
+  let kind = event.kind(&state.read().norm()?.schedule_rules);
+
+  match kind {
+    | Some(kind) => schedule(state, kind, ctx).await,
+    | None => {
+      log::warn!("No schedule kind configured for rules {:?}, ignoring",
+                 event.resources);
+      noop()
+    },
+  }
+}
+
+pub async fn http<S: State>(state: &S,
+                            req: lamb::HttpRequest,
+                            ctx: &lamb::Context)
+                            -> Result<Value, crate::AnyError> {
+  let router = routes::<S>();
+  let format = req.format;
+  let origin = req.header("Origin").map(String::from);
+  let cors =
+    state.read()
+         .map(cors::Cors::new)
+         .tap_err(|e| log::error!("Failed to read CORS config: {:#?}", e))
+         .unwrap_or_default();
+
+  let response = match req.decode_body() {
+    | Err(e) => {
+      let msg = format!("Request body is not valid base64: {}", e);
+      HttpError::BadRequest(msg).response()
+    },
+    | Ok(req) if cors::Cors::is_preflight(&req) => {
+      match router.methods(&req.path).as_slice() {
+        | [] => {
+          HttpError::NotFound(format!("{} was not found", req.path)).response()
+        },
+        | methods => cors.preflight(&req, methods),
+      }
+    },
+    | Ok(req) => route(state, &router, req, ctx).await,
+  };
+
+  let response = response.header(REQUEST_ID_HEADER, &ctx.request_id);
+
+  let response = match origin {
+    | Some(origin) => cors.apply(&origin, response),
+    | None => response,
+  };
+
+  response.into_value(format)
+          .norm()
+          .tap_err(|e| log::error!("Failed to serialize HTTP response: {:#?}", e))
+}
+
+async fn route<S: State>(state: &S,
+                         router: &router::Router<S>,
+                         req: lamb::HttpRequest,
+                         ctx: &lamb::Context)
+                         -> lamb::HttpResponse {
+  use router::Resolved;
+
+  match router.resolve(req.method, &req.path) {
+    | Resolved::Found(route, params) => {
+      let authed = if route.public {
+        Ok(Ok(()))
+      } else {
+        state.read()
+             .norm()
+             .map(|app| auth::authenticate(app, &req, Utc::now()))
+      };
+
+      match authed {
+        | Err(e) => error_response(e),
+        | Ok(Err(e)) => {
+          log::warn!("Rejecting {} {}: {}", req.method, req.path, e);
+          HttpError::from(&e).response()
+                             .header("WWW-Authenticate", "Bearer")
+        },
+        | Ok(Ok(())) => {
+          let key = req.header(idempotency::HEADER)
+                       .filter(|_| req.method.is_mutating() && !route.public)
+                       .map(String::from);
+
+          match key {
+            | Some(key) => {
+              idempotent(state, route, key, req, params, ctx).await
+            },
+            | None => handle_route(state, route, req, params, ctx).await,
+          }
+        },
+      }
+    },
+    | Resolved::MethodNotAllowed(allow) => {
+      let allow = allow.iter()
+                       .map(ToString::to_string)
+                       .collect::<Vec<_>>()
+                       .join(", ");
+
+      let msg = format!("{} {} is not allowed", req.method, req.path);
+      HttpError::MethodNotAllowed(msg).response()
+                                      .header("Allow", allow)
+    },
+    | Resolved::NotFound => {
+      let msg = format!("{} {} was not found", req.method, req.path);
+      HttpError::NotFound(msg).response()
+    },
+  }
+}
+
+async fn handle_route<S: State>(state: &S,
+                                route: &router::Route<S>,
+                                req: lamb::HttpRequest,
+                                params: router::Params,
+                                ctx: &lamb::Context)
+                                -> lamb::HttpResponse {
+  let req = router::Req { http: req,
+                          params,
+                          ctx: ctx.clone() };
+
+  (route.handler)(state, req).await
+                             .tap(|r| log::info!("Responding: {:#?}", r))
+                             .map_err(error_response)
+                             .open()
+}
+
+/// Handle a request carrying an idempotency key, replaying the stored
+/// response if the request has been handled before
+async fn idempotent<S: State>(state: &S,
+                              route: &router::Route<S>,
+                              key: String,
+                              req: lamb::HttpRequest,
+                              params: router::Params,
+                              ctx: &lamb::Context)
+                              -> lamb::HttpResponse {
+  use idempotency::Lookup;
+
+  let now = Utc::now();
+  let fingerprint = idempotency::fingerprint(&req);
+  let app = match state.read().norm() {
+    | Ok(app) => app,
+    | Err(e) => return error_response(e),
+  };
+
+  match idempotency::lookup(&app.idempotency, &key, &fingerprint, now) {
+    | Lookup::Replay(response) => response,
+    | Lookup::Conflict(e) => e.response(),
+    | Lookup::Miss => {
+      let response = handle_route(state, route, req, params, ctx).await;
+
+      if let Err(e) =
+        idempotency::store(state, &key, fingerprint, &response, now)
+      {
+        log::error!("Failed to store response for {}: {:#?}", key, e);
+      }
+
+      response
+    },
+  }
+}
+
+fn routes<S: State>() -> router::Router<S> {
+  use lamb::HttpMethod::*;
+
+  router::Router::new().route(Post, "/execute", execute::<S>)
+                       .doc(EXECUTE_DOC)
+                       .route(Post, "/jobs/{name}", execute_job::<S>)
+                       .doc(EXECUTE_JOB_DOC)
+                       .route(Get, "/events", events::get::<S>)
+                       .doc(events::DOC)
+                       .route(Get, "/summary", summary::preview::<S>)
+                       .doc(summary::PREVIEW_DOC)
+                       .route(Post, "/notify", notify::post::<S>)
+                       .doc(notify::DOC)
+                       .route(Get, "/auth/status", ad::status::<S>)
+                       .doc(ad::STATUS_DOC)
+                       .route(Post, "/auth/start", ad::start::<S>)
+                       .doc(ad::START_DOC)
+                       .route(Delete, "/auth", ad::sign_out::<S>)
+                       .doc(ad::SIGN_OUT_DOC)
+                       .public(Post, "/webhooks/github", github::post::<S>)
+                       .doc(github::DOC)
+                       .public(Post, "/webhooks/{name}", webhook::post::<S>)
+                       .doc(webhook::DOC)
+                       .public(Get, "/openapi.json", openapi::get::<S>)
+                       .doc(openapi::DOC)
+}
+
+const EXECUTE_DOC: openapi::Doc =
+  openapi::Doc::new("Run the jobs that are due")
+    .response(openapi::schema::<JobsResponse>);
+
+const EXECUTE_JOB_DOC: openapi::Doc =
+  openapi::Doc::new("Run a job, due or not")
+    .response(openapi::schema::<JobsResponse>);
+
+#[derive(Debug, Ser, JsonSchema)]
+struct JobsResponse {
+  success: bool,
+  jobs: Vec<crate::jobs::Report>,
+}
+
+fn execute<S: State>(state: &S,
+                     req: router::Req)
+                     -> BoxFuture<'_, Result<lamb::HttpResponse, AnyError>> {
+  Box::pin(async move { jobs_response(jobs(state, None, &req.ctx).await?) })
+}
+
+fn execute_job<S: State>(
+  state: &S,
+  req: router::Req)
+  -> BoxFuture<'_, Result<lamb::HttpResponse, AnyError>> {
+  Box::pin(async move {
+    let name = req.params
+                  .get("name")
+                  .map(String::as_str)
+                  .unwrap_or_default();
+
+    if crate::jobs::registry::<S>().get(name).is_none() {
+      let msg = format!("No job named {}", name);
+      return Err(HttpError::NotFound(msg).into());
+    }
+
+    jobs_response(jobs(state, Some(name), &req.ctx).await?)
+  })
+}
+
+fn jobs_response(reports: Vec<crate::jobs::Report>)
+                 -> Result<lamb::HttpResponse, AnyError> {
+  let success = reports.iter().all(|r| r.ok);
+
+  lamb::HttpResponse::new().body_json(JobsResponse { success,
+                                                     jobs: reports })
+                           .norm()
+}
+
+fn error_response(e: AnyError) -> lamb::HttpResponse {
+  let err = HttpError::from_any(e.as_ref());
+
+  if err.status() >= 500 {
+    log::error!("Responding {}: {:#?}", err.status(), e);
+  } else {
+    log::warn!("Responding {}: {}", err.status(), err);
+  }
+
+  err.response()
+}
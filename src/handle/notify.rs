@@ -0,0 +1,83 @@
+//! `POST /notify`, relaying a notification through the configured notifiers.
+//!
+//! Body:
+//! `{"title": "...", "body": "...", "priority": "high", "notifiers": ["pushbullet"]}`
+//! where `priority` (`low`, `normal` or `high`) and `notifiers` are optional.
+//! Responds with how delivery went for each notifier.
+
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::{Deserialize as De, Serialize as Ser};
+
+use super::{openapi::{self, Doc},
+            router,
+            HttpError,
+            State};
+use crate::{app::state::Ext,
+            lamb::HttpResponse,
+            notify::{Delivery, Priority},
+            prelude::*,
+            AnyError};
+
+#[derive(Debug, De, JsonSchema)]
+struct Request {
+  title: String,
+  body: String,
+
+  #[serde(default)]
+  priority: Priority,
+
+  /// Names of the notifiers to send through, or all of them if omitted
+  #[serde(default)]
+  notifiers: Option<Vec<String>>,
+}
+
+pub const DOC: Doc =
+  Doc::new("Send a notification").request(openapi::schema::<Request>)
+                                 .response(openapi::schema::<Response>);
+
+#[derive(Debug, Ser, JsonSchema)]
+struct Response {
+  success: bool,
+  deliveries: Vec<Delivery>,
+}
+
+pub fn post<S: State>(state: &S,
+                      req: router::Req)
+                      -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    let body = match req.http.body_json::<Request>() {
+      | Ok(body) => body,
+      | Err(e) => {
+        let msg = format!("Invalid body: {}", e);
+        return Err(HttpError::BadRequest(msg).into());
+      },
+    };
+
+    let app = state.read().norm()?;
+    let unknown =
+      body.notifiers
+          .iter()
+          .flatten()
+          .filter(|name| !app.notifiers.iter().any(|n| n.name() == *name))
+          .cloned()
+          .collect::<Vec<_>>();
+
+    if !unknown.is_empty() {
+      let msg = format!("Unknown notifiers: {}", unknown.join(", "));
+      return Err(HttpError::BadRequest(msg).into());
+    }
+
+    let deliveries = state.notify_each(&body.title,
+                                       &body.body,
+                                       body.priority,
+                                       body.notifiers.as_deref())
+                          .await?;
+
+    let success = deliveries.iter().all(|d| d.ok);
+
+    HttpResponse::new().body_json(Response { success,
+                                             deliveries })
+                       .norm()
+  })
+}
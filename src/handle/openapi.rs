@@ -0,0 +1,408 @@
+//! OpenAPI 3 description of the HTTP API, served at `GET /openapi.json`.
+//!
+//! The document is generated from the route table on every request. Paths,
+//! methods, path parameters, authentication, idempotency keys and error
+//! responses all come from the routes themselves. Each route's [`Doc`]
+//! adds what the router can't know: a summary, query parameters and the
+//! shapes of request and response bodies. Body schemas are derived from
+//! the types that are (de)serialized with [`schema`], so they can't drift
+//! from what's actually sent.
+
+use futures::future::BoxFuture;
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde_json::{json, Map, Value};
+
+use super::{auth, error, idempotency, router, State, REQUEST_ID_HEADER};
+use crate::{lamb::HttpResponse, prelude::*, AnyError};
+
+/// What a route's definition can't say about it
+#[derive(Clone, Copy)]
+pub struct Doc {
+  pub summary: &'static str,
+
+  /// Name and description of each (optional) query parameter
+  pub query: &'static [(&'static str, &'static str)],
+
+  /// JSON schema of the request body, if the route reads one
+  pub request: Option<fn() -> Value>,
+
+  /// Schema of successful responses
+  pub response: fn() -> Value,
+
+  /// Content types successful responses can have
+  pub content_types: &'static [&'static str],
+
+  /// Security requirements of a public route that authenticates requests
+  /// itself
+  pub security: Option<fn() -> Value>,
+}
+
+impl Doc {
+  /// A route responding `{"success": true}`
+  pub const fn new(summary: &'static str) -> Self {
+    Self { summary,
+           query: &[],
+           request: None,
+           response: success,
+           content_types: &["application/json"],
+           security: None }
+  }
+
+  pub const fn query(mut self,
+                     query: &'static [(&'static str, &'static str)])
+                     -> Self {
+    self.query = query;
+    self
+  }
+
+  pub const fn request(mut self, schema: fn() -> Value) -> Self {
+    self.request = Some(schema);
+    self
+  }
+
+  pub const fn response(mut self, schema: fn() -> Value) -> Self {
+    self.response = schema;
+    self
+  }
+
+  pub const fn content_types(mut self,
+                             content_types: &'static [&'static str])
+                             -> Self {
+    self.content_types = content_types;
+    self
+  }
+
+  pub const fn security(mut self, security: fn() -> Value) -> Self {
+    self.security = Some(security);
+    self
+  }
+}
+
+pub const DOC: Doc =
+  Doc::new("This OpenAPI document").response(|| json!({ "type": "object" }));
+
+/// The OpenAPI schema of how a type is (de)serialized
+pub fn schema<T: JsonSchema>() -> Value {
+  let mut settings = SchemaSettings::openapi3();
+  settings.inline_subschemas = true;
+  settings.meta_schema = None;
+
+  let schema = settings.into_generator().into_root_schema_for::<T>();
+  serde_json::to_value(schema).expect("schemas should serialize")
+}
+
+/// `{"success": true}`, for routes that respond with nothing else
+fn success() -> Value {
+  json!({
+    "type": "object",
+    "required": ["success"],
+    "properties": {"success": {"type": "boolean"}},
+  })
+}
+
+fn error_response(description: &str) -> Value {
+  json!({
+    "description": description,
+    "content": {
+      "application/json": {"schema": {"$ref": "#/components/schemas/Error"}},
+    },
+  })
+}
+
+fn path_params(pattern: &str) -> impl Iterator<Item = &str> {
+  pattern.split('/')
+         .filter(|s| s.starts_with('{') && s.ends_with('}'))
+         .map(|s| &s[1..s.len() - 1])
+}
+
+fn operation<S>(route: &router::Route<S>) -> Value {
+  let doc = route.doc.unwrap_or_else(|| Doc::new(""));
+  let idempotent = route.method.is_mutating() && !route.public;
+  let authenticated = !route.public || doc.security.is_some();
+
+  let path = path_params(route.pattern).map(|name| {
+                                         json!({
+                                           "name": name,
+                                           "in": "path",
+                                           "required": true,
+                                           "schema": {"type": "string"},
+                                         })
+                                       });
+  let query = doc.query.iter().map(|(name, description)| {
+                                json!({
+                                  "name": name,
+                                  "in": "query",
+                                  "description": description,
+                                  "schema": {"type": "string"},
+                                })
+                              });
+  let key = Some(json!({
+                   "name": idempotency::HEADER,
+                   "in": "header",
+                   "description": "Replay the response to an earlier \
+                                   request with this key",
+                   "schema": {"type": "string"},
+                 })).filter(|_| idempotent);
+
+  let content =
+    doc.content_types
+       .iter()
+       .map(|ct| (ct.to_string(), json!({"schema": (doc.response)()})))
+       .collect::<Map<_, _>>();
+
+  let mut responses = Map::new();
+  responses.insert("200".into(),
+                   json!({
+                     "description": "OK",
+                     "headers": {
+                       REQUEST_ID_HEADER: {"schema": {"type": "string"}},
+                     },
+                     "content": content,
+                   }));
+  if doc.request.is_some() || !doc.query.is_empty() {
+    responses.insert("400".into(), error_response("Invalid request"));
+  }
+  if authenticated {
+    responses.insert("401".into(), error_response("Not authenticated"));
+  }
+  if idempotent {
+    let msg = "Idempotency key was used for a different request";
+    responses.insert("409".into(), error_response(msg));
+  }
+  responses.insert("default".into(), error_response("Error"));
+
+  let mut op = json!({
+    "operationId": operation_id(route),
+    "summary": doc.summary,
+    "parameters": path.chain(query).chain(key).collect::<Vec<_>>(),
+    "responses": responses,
+  });
+
+  if let Some(schema) = doc.request {
+    op["requestBody"] = json!({
+      "required": true,
+      "content": {"application/json": {"schema": schema()}},
+    });
+  }
+
+  match doc.security {
+    | Some(security) => op["security"] = security(),
+    | None if route.public => op["security"] = json!([]),
+    | None => (),
+  }
+
+  op
+}
+
+/// e.g. `post_jobs_name` for `POST /jobs/{name}`
+fn operation_id<S>(route: &router::Route<S>) -> String {
+  let segments =
+    route.pattern
+         .split('/')
+         .filter(|s| !s.is_empty())
+         .map(|s| s.trim_matches(&['{', '}'][..]).replace('-', "_"));
+
+  std::iter::once(route.method.to_string().to_lowercase()).chain(segments)
+                                                          .collect::<Vec<_>>()
+                                                          .join("_")
+}
+
+/// The OpenAPI document describing a router's routes
+pub fn document<S>(router: &router::Router<S>) -> Value {
+  let mut paths = Map::new();
+
+  for route in router.routes() {
+    let path = paths.entry(route.pattern).or_insert_with(|| json!({}));
+    path[route.method.to_string().to_lowercase()] = operation(route);
+  }
+
+  json!({
+    "openapi": "3.0.3",
+    "info": {
+      "title": env!("CARGO_PKG_NAME"),
+      "version": env!("CARGO_PKG_VERSION"),
+    },
+    "security": [{"bearer": []}, {"signature": [], "timestamp": []}],
+    "components": {
+      "securitySchemes": {
+        "bearer": {"type": "http", "scheme": "bearer"},
+        "signature": {
+          "type": "apiKey",
+          "in": "header",
+          "name": auth::SIGNATURE_HEADER,
+          "description": "sha256=<HMAC-SHA256 of \
+                          {timestamp}.{METHOD}.{path}.{query}.{raw body}>, \
+                          where {query} is the query parameters sorted by \
+                          name and form-urlencoded",
+        },
+        "timestamp": {
+          "type": "apiKey",
+          "in": "header",
+          "name": auth::TIMESTAMP_HEADER,
+          "description": "Unix seconds the request was signed at",
+        },
+        "token": {
+          "type": "apiKey",
+          "in": "query",
+          "name": "token",
+          "description": "Token of the webhook rule, instead of a bearer \
+                          token",
+        },
+      },
+      "schemas": {"Error": schema::<error::Envelope>()},
+    },
+    "paths": paths,
+  })
+}
+
+pub fn get<S: State>(_: &S,
+                     _: router::Req)
+                     -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    HttpResponse::new().body_json(document(&super::routes::<S>()))
+                       .norm()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::Utc;
+
+  use super::*;
+  use crate::{app::state,
+              calendar::{Cat, Event, Personal},
+              handle::{github, HttpError},
+              jobs::Report,
+              notify::Delivery};
+
+  /// Whether a value has the shape a schema describes, as far as the
+  /// keywords derived schemas use go
+  fn conforms(value: &Value, schema: &Value) -> bool {
+    let all = |key: &str| {
+      schema[key].as_array()
+                 .map(|ss| ss.iter().all(|s| conforms(value, s)))
+                 .unwrap_or(true)
+    };
+    let one = |key: &str| {
+      schema[key].as_array()
+                 .map(|ss| {
+                   ss.iter().filter(|s| conforms(value, s)).count() == 1
+                 })
+                 .unwrap_or(true)
+    };
+
+    if value.is_null() && schema["nullable"] == true {
+      return true;
+    }
+
+    let typed = match (schema["type"].as_str(), value) {
+      | (None, _) => true,
+      | (Some("object"), Value::Object(props)) => {
+        let required =
+          schema["required"].as_array().cloned().unwrap_or_default();
+        let closed = schema["additionalProperties"] == false;
+
+        required.iter()
+                .all(|k| props.contains_key(k.as_str().unwrap_or_default()))
+        && props.iter()
+                .all(|(k, v)| match schema["properties"].get(k) {
+                  | Some(s) => conforms(v, s),
+                  | None => !closed,
+                })
+      },
+      | (Some("array"), Value::Array(items)) => {
+        items.iter().all(|v| conforms(v, &schema["items"]))
+      },
+      | (Some("string"), Value::String(_))
+      | (Some("boolean"), Value::Bool(_)) => true,
+      | (Some("integer"), Value::Number(n)) => n.is_i64() || n.is_u64(),
+      | (Some("number"), Value::Number(_)) => true,
+      | _ => false,
+    };
+    let listed = schema["enum"].as_array()
+                               .map(|vs| vs.contains(value))
+                               .unwrap_or(true);
+
+    typed && listed && all("allOf") && one("oneOf") && {
+      schema["anyOf"].as_array()
+                     .map(|ss| ss.iter().any(|s| conforms(value, s)))
+                     .unwrap_or(true)
+    }
+  }
+
+  fn check<T: serde::Serialize + JsonSchema>(value: T) {
+    let value = serde_json::to_value(value).unwrap();
+    let schema = schema::<T>();
+
+    assert!(conforms(&value, &schema),
+            "{} doesn't match {}",
+            value,
+            schema);
+  }
+
+  #[test]
+  pub fn document_should_describe_every_route() {
+    // ARRANGE
+    let router = super::super::routes::<state::S>();
+
+    // ACT
+    let doc = document(&router);
+
+    // ASSERT
+    for route in router.routes() {
+      let method = route.method.to_string().to_lowercase();
+      let op = &doc["paths"][route.pattern][&method];
+
+      assert!(route.doc.is_some(),
+              "{} {} has no Doc",
+              method,
+              route.pattern);
+      assert!(op["responses"]["200"].is_object());
+    }
+
+    let job = &doc["paths"]["/jobs/{name}"]["post"];
+    assert_eq!(job["operationId"], "post_jobs_name");
+    assert_eq!(job["parameters"][0]["name"], "name");
+    assert_eq!(job["parameters"][1]["name"], idempotency::HEADER);
+    assert_eq!(doc["paths"]["/webhooks/github"]["post"]["security"],
+               json!([]));
+    assert_eq!(doc["paths"]["/webhooks/{name}"]["post"]["security"][0],
+               json!({"bearer": []}));
+  }
+
+  #[test]
+  pub fn schemas_should_match_serialized_values() {
+    // ARRANGE
+    let event = |cat| Event { cat,
+                              title: "Standup".into(),
+                              time_start: Utc::now(),
+                              time_end: Utc::now(),
+                              location: None,
+                              all_day: false };
+    let error = HttpError::NotFound("No job named x".into()).response();
+
+    // ACT
+    let error =
+      serde_json::from_str::<Value>(error.body.as_deref().unwrap()).unwrap();
+
+    // ASSERT
+    check(vec![event(Cat::Work), event(Cat::Personal(Personal::Med))]);
+    check(Event { location: Some("Room 1".into()),
+                  ..event(Cat::Work) });
+    check(vec![Delivery { notifier: "pushbullet".into(),
+                          ok: true,
+                          error: None },
+               Delivery { notifier: "slack".into(),
+                          ok: false,
+                          error: Some("down".into()) }]);
+    check(Report { job: "refresh_auth".into(),
+                   ok: true,
+                   error: None });
+    check(github::Forwarded { success: true,
+                              forwarded: false });
+    assert!(conforms(&error, &schema::<error::Envelope>()));
+    assert!(!conforms(&json!({"success": false}),
+                      &schema::<error::Envelope>()));
+    assert!(!conforms(&json!([{"cat": "Play"}]), &schema::<Vec<Event>>()));
+  }
+}
@@ -0,0 +1,92 @@
+//! Handlers for messages pushed to the app through SQS or SNS.
+//!
+//! SQS batches report the messages that failed as `batchItemFailures`,
+//! so the event source mapping must have `ReportBatchItemFailures` enabled
+//! for only those messages to be retried. An SNS event fails as a whole
+//! if any of its messages fail, so Lambda retries it.
+
+use serde_json::{json, Value};
+
+use super::State;
+use crate::{app,
+            app::state::Ext,
+            lamb::{queue::Message, Context, SnsEvent, SqsEvent},
+            prelude::*,
+            AnyError};
+
+/// Handle an SQS batch, reporting which messages failed
+pub async fn sqs<S: State>(state: &S,
+                           event: SqsEvent,
+                           ctx: &Context)
+                           -> Result<Value, AnyError> {
+  log::info!("Handling {} SQS messages", event.records.len());
+
+  let mut failures = vec![];
+
+  for record in event.records {
+    let result = record.message()
+                       .norm()
+                       .bind_async(|msg| message(state, msg, ctx))
+                       .await;
+
+    if let Err(e) = result {
+      log::error!("> SQS message {} failed: {:#?}", record.message_id, e);
+      failures.push(json!({ "itemIdentifier": record.message_id }));
+    }
+  }
+
+  Ok(json!({ "batchItemFailures": failures }))
+}
+
+/// Handle the records of an SNS event, failing if any of them fail
+pub async fn sns<S: State>(state: &S,
+                           event: SnsEvent,
+                           ctx: &Context)
+                           -> Result<Value, AnyError> {
+  log::info!("Handling {} SNS messages", event.records.len());
+
+  let mut failed = vec![];
+
+  for record in event.records {
+    let sns = record.sns;
+    let result = sns.message()
+                    .norm()
+                    .bind_async(|msg| message(state, msg, ctx))
+                    .await;
+
+    if let Err(e) = result {
+      log::error!("> SNS message {} failed: {:#?}", sns.message_id, e);
+      failed.push(format!("{}: {}", sns.message_id, e));
+    }
+  }
+
+  if failed.is_empty() {
+    super::noop()
+  } else {
+    Err(app::Error::Other(format!("Messages failed:\n{}", failed.join("\n")))).norm()
+  }
+}
+
+/// Do what a queued message asks
+pub async fn message<S: State>(state: &S,
+                               msg: Message,
+                               ctx: &Context)
+                               -> Result<(), AnyError> {
+  match msg {
+    | Message::Notify { title, body } => state.notify(&title, &body).await,
+    | Message::Job { name } => {
+      let report = super::jobs(state, Some(&name), ctx).await?
+                                                       .into_iter()
+                                                       .find(|r| !r.ok);
+
+      match report {
+        | Some(r) => {
+          Err(app::Error::Other(format!("Job {} failed: {}",
+                                        r.job,
+                                        r.error.unwrap_or_default()))).norm()
+        },
+        | None => Ok(()),
+      }
+    },
+  }
+}
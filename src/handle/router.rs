@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use futures::future::BoxFuture;
+
+use super::openapi::Doc;
+use crate::{lamb::{Context, HttpMethod, HttpRequest, HttpResponse},
+            AnyError};
+
+/// Values captured by `{name}` segments in a route pattern
+pub type Params = HashMap<String, String>;
+
+pub type Handler<S> =
+  for<'a> fn(&'a S, Req) -> BoxFuture<'a, Result<HttpResponse, AnyError>>;
+
+/// An HTTP request that matched a route
+#[derive(Debug)]
+pub struct Req {
+  pub http: HttpRequest,
+  pub params: Params,
+  pub ctx: Context,
+}
+
+pub struct Route<S> {
+  pub method: HttpMethod,
+  pub pattern: &'static str,
+  pub handler: Handler<S>,
+
+  /// Whether the route is reachable without inbound authentication,
+  /// e.g. because the handler verifies requests itself
+  pub public: bool,
+
+  pub doc: Option<Doc>,
+}
+
+/// Outcome of looking up a `(method, path)` pair in a [`Router`]
+pub enum Resolved<'r, S> {
+  Found(&'r Route<S>, Params),
+
+  /// The path matched at least one route, but none with this method.
+  ///
+  /// Contains the methods that would have matched, for the `Allow` header.
+  MethodNotAllowed(Vec<HttpMethod>),
+
+  NotFound,
+}
+
+/// Route table mapping `(method, path pattern)` to handlers.
+///
+/// Patterns are `/`-separated, and a segment written as `{name}`
+/// captures that segment of the path into [`Params`].
+/// Routes are tried in the order they were added.
+pub struct Router<S> {
+  routes: Vec<Route<S>>,
+}
+
+impl<S> Router<S> {
+  pub fn new() -> Self {
+    Self { routes: vec![] }
+  }
+
+  pub fn route(mut self,
+               method: HttpMethod,
+               pattern: &'static str,
+               handler: Handler<S>)
+               -> Self {
+    self.routes.push(Route { method,
+                             pattern,
+                             handler,
+                             public: false,
+                             doc: None });
+    self
+  }
+
+  /// Add a route that skips inbound authentication
+  pub fn public(mut self,
+                method: HttpMethod,
+                pattern: &'static str,
+                handler: Handler<S>)
+                -> Self {
+    self.routes.push(Route { method,
+                             pattern,
+                             handler,
+                             public: true,
+                             doc: None });
+    self
+  }
+
+  /// Document the route added last, for [`super::openapi`]
+  pub fn doc(mut self, doc: Doc) -> Self {
+    if let Some(route) = self.routes.last_mut() {
+      route.doc = Some(doc);
+    }
+    self
+  }
+
+  pub fn routes(&self) -> &[Route<S>] {
+    &self.routes
+  }
+
+  pub fn resolve(&self, method: HttpMethod, path: &str) -> Resolved<'_, S> {
+    let mut allow = Vec::<HttpMethod>::new();
+
+    for route in &self.routes {
+      match match_path(route.pattern, path) {
+        | Some(params) if route.method == method => {
+          return Resolved::Found(route, params)
+        },
+        | Some(_) if !allow.contains(&route.method) => allow.push(route.method),
+        | _ => (),
+      }
+    }
+
+    if allow.is_empty() {
+      Resolved::NotFound
+    } else {
+      Resolved::MethodNotAllowed(allow)
+    }
+  }
+
+  /// Every method routed for `path`
+  pub fn methods(&self, path: &str) -> Vec<HttpMethod> {
+    let mut methods = Vec::<HttpMethod>::new();
+
+    self.routes
+        .iter()
+        .filter(|r| match_path(r.pattern, path).is_some())
+        .for_each(|r| {
+          if !methods.contains(&r.method) {
+            methods.push(r.method)
+          }
+        });
+
+    methods
+  }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+  path.split('/').filter(|s| !s.is_empty())
+}
+
+fn match_path(pattern: &str, path: &str) -> Option<Params> {
+  let mut params = Params::new();
+  let mut pattern_segs = segments(pattern);
+  let mut path_segs = segments(path);
+
+  loop {
+    match (pattern_segs.next(), path_segs.next()) {
+      | (None, None) => return Some(params),
+      | (Some(pat), Some(seg)) if pat.starts_with('{') && pat.ends_with('}') =>
+      {
+        params.insert(pat[1..pat.len() - 1].to_string(), seg.to_string());
+      },
+      | (Some(pat), Some(seg)) if pat == seg => (),
+      | _ => return None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn handler(_: &(), _: Req) -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+    Box::pin(async { Ok(HttpResponse::new()) })
+  }
+
+  fn router() -> Router<()> {
+    Router::new().route(HttpMethod::Post, "/execute", handler)
+                 .route(HttpMethod::Get, "/jobs/{name}", handler)
+                 .route(HttpMethod::Post, "/jobs/{name}", handler)
+  }
+
+  #[test]
+  pub fn resolve_should_capture_path_params() {
+    // ARRANGE
+    let router = router();
+
+    // ACT
+    let resolved = router.resolve(HttpMethod::Get, "/jobs/refresh/");
+
+    // ASSERT
+    match resolved {
+      | Resolved::Found(route, params) => {
+        assert_eq!(route.pattern, "/jobs/{name}");
+        assert_eq!(params.get("name").map(String::as_str), Some("refresh"));
+      },
+      | _ => panic!("expected a route to match"),
+    }
+  }
+
+  #[test]
+  pub fn resolve_should_list_allowed_methods() {
+    // ARRANGE
+    let router = router();
+
+    // ACT
+    let resolved = router.resolve(HttpMethod::Options, "/jobs/refresh");
+
+    // ASSERT
+    match resolved {
+      | Resolved::MethodNotAllowed(allow) => {
+        assert_eq!(allow, vec![HttpMethod::Get, HttpMethod::Post])
+      },
+      | _ => panic!("expected method not allowed"),
+    }
+  }
+
+  #[test]
+  pub fn resolve_should_not_find_unknown_paths() {
+    // ARRANGE
+    let router = router();
+
+    // ACT
+    let resolved = router.resolve(HttpMethod::Post, "/execute/now");
+
+    // ASSERT
+    assert!(matches!(resolved, Resolved::NotFound));
+  }
+}
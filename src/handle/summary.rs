@@ -0,0 +1,751 @@
+//! Summaries of upcoming events, pushed to notifiers on a schedule
+//! or previewed with `GET /summary`.
+//!
+//! Preview query parameters:
+//! - `day`: `today` (default), `tomorrow`, a `YYYY-MM-DD` date or
+//!   a number of days from today, at most a year away
+//! - `week`: `true` to show the whole Monday-to-Sunday week of that day
+//! - `tz`: IANA timezone to count days and render times in, e.g.
+//!   `America/Denver`. Defaults to `App::timezone`.
+//! - `format`: `text` (default, as pushed), `markdown` or `html`
+//! - `template`: name of a template in `App::templates` to render with
+//!   (see [`crate::template`]), instead of the format. Pushed summaries
+//!   and the text format use the one named `summary`, if there is one.
+//!
+//! Each working day lists its focus time: the gaps between timed events
+//! within `App::work_hours` that are at least `App::focus_min_minutes`
+//! (an hour by default) long, e.g. `Focus: 01:00PM–03:30PM (2.5h)`.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+
+use super::{ad, noop, openapi::Doc, router, HttpError, State};
+use crate::{app::{state::Ext, App},
+            calendar::Event,
+            lamb::{HttpResponse, SummaryParams},
+            prelude::*,
+            template::Template,
+            time::{Clock, Days, Range, SystemClock, WorkHours},
+            AnyError};
+
+/// How to render a [`Summary`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+  Text,
+  Markdown,
+  Html,
+}
+
+impl Format {
+  fn content_type(&self) -> &'static str {
+    match self {
+      | Self::Text => "text/plain; charset=utf-8",
+      | Self::Markdown => "text/markdown; charset=utf-8",
+      | Self::Html => "text/html; charset=utf-8",
+    }
+  }
+}
+
+impl FromStr for Format {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      | "text" | "txt" => Ok(Self::Text),
+      | "markdown" | "md" => Ok(Self::Markdown),
+      | "html" => Ok(Self::Html),
+      | _ => Err(format!("Unknown format {:?}", s)),
+    }
+  }
+}
+
+/// How days are labelled, e.g. `Monday 04/05`
+const DAY_FMT: &str = "%A %m/%d";
+
+/// Name of the template in `App::templates` summaries are rendered with
+/// by default, if there is one
+pub const DEFAULT_TEMPLATE: &str = "summary";
+
+const DEFAULT_FOCUS_MIN_MINUTES: u32 = 60;
+
+/// The events in a window of days, ready to render
+#[derive(Debug)]
+pub struct Summary {
+  pub title: String,
+  pub events: Vec<Event>,
+  days: Days,
+  range: Range,
+  work_hours: WorkHours,
+
+  /// The shortest free time worth calling focus time
+  focus_min: Duration,
+}
+
+impl Summary {
+  /// Fetch the events in the window described by `params`
+  pub async fn build(state: &impl State,
+                     params: SummaryParams,
+                     clock: &impl Clock)
+                     -> Result<Self, AnyError> {
+    params.check().map_err(HttpError::BadRequest)?;
+
+    let app = state.read().norm()?;
+    let tz = params.tz.unwrap_or(app.timezone);
+    let work_hours = app.work_hours;
+    let focus_min = app.focus_min_minutes.unwrap_or(DEFAULT_FOCUS_MIN_MINUTES);
+
+    let days = Days::new(tz);
+    let first = days.today(clock) + Duration::days(params.offset_days);
+    let range = if params.week {
+      days.week(first)
+    } else {
+      days.range(first, params.span_days)
+    };
+
+    let mut events = state.get_events(range.start, range.end).await?;
+
+    // sort by start date ascending
+    events.sort_by(|a, b| std::cmp::Ord::cmp(&a.time_start, &b.time_start));
+
+    let title = match params.title {
+      | Some(title) => title,
+      | None if params.week => {
+        format!("Week of {}", range.first.format("%b %d"))
+      },
+      | None => format!("Events {} - {}",
+                        range.first.format("%b %d"),
+                        range.last().format("%b %d")),
+    };
+
+    Ok(Self { title,
+              events,
+              days,
+              range,
+              work_hours,
+              focus_min: Duration::minutes(i64::from(focus_min)) })
+  }
+
+  fn fmt_time(&self, dt: DateTime<Utc>) -> String {
+    dt.with_timezone(&self.days.tz)
+      .format("%I:%M%p")
+      .to_string()
+  }
+
+  /// When an event starts and ends, e.g. `09:00AM - 10:00AM`
+  fn fmt_span(&self, event: &Event) -> String {
+    if event.all_day {
+      "All day".into()
+    } else {
+      format!("{} - {}",
+              self.fmt_time(event.time_start),
+              self.fmt_time(event.time_end))
+    }
+  }
+
+  /// A block of focus time, e.g. `01:00PM–03:30PM (2.5h)`
+  fn fmt_focus(&self, (start, end): Span) -> String {
+    format!("{}–{} ({})",
+            self.fmt_time(start),
+            self.fmt_time(end),
+            fmt_hours(end - start))
+  }
+
+  /// Lines noting things about a day, each its own paragraph
+  fn render_notes(notes: &[String], format: Format) -> String {
+    notes.iter()
+         .map(|note| match format {
+           | Format::Text => format!("{}\n", note),
+           | Format::Markdown => format!("{}\n\n", note),
+           | Format::Html => format!("<p>{}</p>", note),
+         })
+         .collect()
+  }
+
+  fn render_events(&self, events: &[&Event], format: Format) -> String {
+    let lines = events.iter().map(|e| match format {
+                               | Format::Text => {
+                                 format!("\"{}\" ({})\n{}\n\n",
+                                         e.title,
+                                         e.cat,
+                                         self.fmt_span(e))
+                               },
+                               | Format::Markdown => {
+                                 format!("- **{}** ({}): {}\n",
+                                         e.title,
+                                         e.cat,
+                                         self.fmt_span(e))
+                               },
+                               | Format::Html => {
+                                 format!("<li><strong>{}</strong> ({}): \
+                                          {}</li>",
+                                         escape_html(&e.title),
+                                         e.cat,
+                                         self.fmt_span(e))
+                               },
+                             });
+
+    match format {
+      | Format::Html => format!("<ul>{}</ul>", lines.collect::<String>()),
+      | _ => lines.collect(),
+    }
+  }
+
+  /// The events of each day in the summary
+  fn by_day(&self) -> Vec<Day<'_>> {
+    self.range
+        .days()
+        .map(|date| {
+          let bounds = self.days.day(date);
+          let (all_day, timed): (Vec<&Event>, Vec<&Event>) =
+            self.events
+                .iter()
+                .filter(|e| is_on(e, &bounds))
+                .partition(|e| e.all_day);
+          let scheduled = scheduled(&timed, &bounds);
+          let focus = self.work_hours
+                          .on(&self.days, date)
+                          .map(|work| focus(&timed, work, self.focus_min))
+                          .unwrap_or_default();
+
+          Day { date,
+                all_day,
+                timed,
+                scheduled,
+                focus }
+        })
+        .collect()
+  }
+
+  /// Render a section per day, headed by how long is scheduled that day,
+  /// with the day's all-day events and focus time listed apart from
+  /// the rest
+  fn render_days(&self, format: Format) -> String {
+    self.by_day()
+        .into_iter()
+        .map(|Day { date,
+                    all_day,
+                    timed,
+                    scheduled,
+                    focus, }| {
+               let heading = match scheduled {
+                 | d if d == Duration::zero() => {
+                   format!("{} (nothing scheduled)", date.format(DAY_FMT))
+                 },
+                 | d => format!("{} ({} scheduled)",
+                                date.format(DAY_FMT),
+                                fmt_hours(d)),
+               };
+
+               let all_day = all_day.iter()
+                                    .map(|e| match format {
+                                      | Format::Html => escape_html(&e.title),
+                                      | _ => e.title.clone(),
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+
+               let notes =
+                 Some(all_day).filter(|s| !s.is_empty())
+                              .map(|s| format!("All day: {}", s))
+                              .into_iter()
+                              .chain(focus.iter().map(|&block| {
+                                                   format!("Focus: {}",
+                                                     self.fmt_focus(block))
+                                                 }))
+                              .collect::<Vec<_>>();
+               let notes = Self::render_notes(&notes, format);
+               let events = self.render_events(&timed, format);
+
+               match format {
+                 | Format::Text => {
+                   format!("{}\n{}\n{}", heading, notes, events)
+                 },
+                 | Format::Markdown => {
+                   format!("## {}\n\n{}{}\n", heading, notes, events)
+                 },
+                 | Format::Html => {
+                   let events = Some(events).filter(|_| !timed.is_empty())
+                                            .unwrap_or_default();
+
+                   format!("<h2>{}</h2>{}{}", heading, notes, events)
+                 },
+               }
+             })
+        .collect()
+  }
+
+  /// Render the body of the summary; the title is rendered only
+  /// by formats that have a place for it.
+  ///
+  /// Summaries of more than one day are grouped by day.
+  pub fn render(&self, format: Format) -> String {
+    let body = if self.range.days().nth(1).is_some() {
+      self.render_days(format)
+    } else {
+      let focus = self.by_day()
+                      .iter()
+                      .flat_map(|day| &day.focus)
+                      .map(|&block| format!("Focus: {}", self.fmt_focus(block)))
+                      .collect::<Vec<_>>();
+      let mut notes = Self::render_notes(&focus, format);
+      if format == Format::Text && !notes.is_empty() {
+        notes.push('\n');
+      }
+      let events =
+        self.render_events(&self.events.iter().collect::<Vec<_>>(), format);
+
+      format!("{}{}", notes, events)
+    };
+
+    match format {
+      | Format::Text => body,
+      | Format::Markdown => format!("# {}\n\n{}", self.title, body),
+      | Format::Html => {
+        format!("<h1>{}</h1>{}", escape_html(&self.title), body)
+      },
+    }
+  }
+
+  fn event_context(&self, event: &Event) -> Value {
+    json!({
+      "title": event.title,
+      "cat": event.cat.to_string(),
+      "location": event.location,
+      "all_day": event.all_day,
+      "start": self.fmt_time(event.time_start),
+      "end": self.fmt_time(event.time_end),
+      "span": self.fmt_span(event),
+      "start_utc": event.time_start,
+      "end_utc": event.time_end,
+    })
+  }
+
+  /// What templates are rendered against
+  pub fn context(&self) -> Value {
+    let hours = |d: Duration| d.num_minutes() as f64 / 60.0;
+    let events = |es: &[&Event]| {
+      es.iter().map(|e| self.event_context(e)).collect::<Vec<_>>()
+    };
+
+    let focus = |blocks: &[Span]| {
+      blocks.iter()
+            .map(|&(start, end)| {
+              json!({
+                "start": self.fmt_time(start),
+                "end": self.fmt_time(end),
+                "hours": hours(end - start),
+                "span": self.fmt_focus((start, end)),
+              })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let days = self.by_day();
+    let scheduled = days.iter()
+                        .fold(Duration::zero(), |sum, d| sum + d.scheduled);
+    let focused = days.iter()
+                      .flat_map(|d| &d.focus)
+                      .fold(Duration::zero(), |sum, (s, e)| sum + (*e - *s));
+    let days = days.iter()
+                   .map(|day| {
+                     json!({
+                       "date": day.date.to_string(),
+                       "label": day.date.format(DAY_FMT).to_string(),
+                       "weekday": day.date.format("%A").to_string(),
+                       "scheduled_hours": hours(day.scheduled),
+                       "scheduled": fmt_hours(day.scheduled),
+                       "all_day": events(&day.all_day),
+                       "events": events(&day.timed),
+                       "focus": focus(&day.focus),
+                     })
+                   })
+                   .collect::<Vec<_>>();
+
+    json!({
+      "title": self.title,
+      "tz": self.days.tz.name(),
+      "day": self.range.first.format(DAY_FMT).to_string(),
+      "first": self.range.first.to_string(),
+      "last": self.range.last().to_string(),
+      "days": days,
+      "events": events(&self.events.iter().collect::<Vec<_>>()),
+      "stats": {
+        "events": self.events.len(),
+        "all_day": self.events.iter().filter(|e| e.all_day).count(),
+        "scheduled_hours": hours(scheduled),
+        "scheduled": fmt_hours(scheduled),
+        "focus_hours": hours(focused),
+        "focus": fmt_hours(focused),
+      },
+    })
+  }
+}
+
+/// The template named `name` in `App::templates`, or the default one
+/// if no name is given and there is a default
+pub fn template(app: &App,
+                name: Option<&str>)
+                -> Result<Option<Template>, String> {
+  let (name, src) = match name {
+    | Some(name) => match app.templates.get(name) {
+      | Some(src) => (name, src),
+      | None => return Err(format!("No template named {}", name)),
+    },
+    | None => match app.templates.get(DEFAULT_TEMPLATE) {
+      | Some(src) => (DEFAULT_TEMPLATE, src),
+      | None => return Ok(None),
+    },
+  };
+
+  src.parse::<Template>()
+     .map(Some)
+     .map_err(|e| format!("Template {} is invalid: {}", name, e))
+}
+
+/// The events of one day of a [`Summary`]
+struct Day<'a> {
+  date: NaiveDate,
+  all_day: Vec<&'a Event>,
+  timed: Vec<&'a Event>,
+
+  /// How much of the day the timed events take up
+  scheduled: Duration,
+
+  /// Free time during working hours
+  focus: Vec<Span>,
+}
+
+/// When something starts and ends
+type Span = (DateTime<Utc>, DateTime<Utc>);
+
+/// Whether an event happens during a day
+fn is_on(event: &Event, day: &Range) -> bool {
+  if event.all_day {
+    // all-day events are dated in UTC rather than the local timezone
+    let dates =
+      event.time_start.naive_utc().date()..event.time_end.naive_utc().date();
+    dates.contains(&day.first)
+  } else {
+    event.time_start < day.end
+    && (event.time_start >= day.start || event.time_end > day.start)
+  }
+}
+
+/// How much of a day events take up, counting time that events
+/// overlap only once
+fn scheduled(events: &[&Event], day: &Range) -> Duration {
+  let mut spans =
+    events.iter()
+          .map(|e| (e.time_start.max(day.start), e.time_end.min(day.end)))
+          .filter(|(start, end)| start < end)
+          .collect::<Vec<_>>();
+  spans.sort();
+
+  let mut total = Duration::zero();
+  let mut counted_until = day.start;
+
+  for (start, end) in spans {
+    if end > counted_until {
+      total = total + (end - start.max(counted_until));
+      counted_until = end;
+    }
+  }
+
+  total
+}
+
+/// The gaps between events during working hours that are at least
+/// `min` long. All-day events don't take up any time.
+fn focus(events: &[&Event], (start, end): Span, min: Duration) -> Vec<Span> {
+  let mut busy = events.iter()
+                       .filter(|e| !e.all_day)
+                       .map(|e| (e.time_start.max(start), e.time_end.min(end)))
+                       .filter(|(start, end)| start < end)
+                       .collect::<Vec<_>>();
+  busy.sort();
+
+  let mut free = vec![];
+  let mut free_from = start;
+
+  // the end of working hours closes the last gap
+  for (busy_from, busy_until) in busy.into_iter().chain(Some((end, end))) {
+    if busy_from > free_from && busy_from - free_from >= min {
+      free.push((free_from, busy_from));
+    }
+    free_from = free_from.max(busy_until);
+  }
+
+  free
+}
+
+/// e.g. `2.5h`
+fn fmt_hours(d: Duration) -> String {
+  let hours = format!("{:.1}", d.num_minutes() as f64 / 60.0);
+  format!("{}h", hours.trim_end_matches(".0"))
+}
+
+fn escape_html(s: &str) -> String {
+  s.replace('&', "&amp;")
+   .replace('<', "&lt;")
+   .replace('>', "&gt;")
+   .replace('"', "&quot;")
+}
+
+/// Push a summary to every notifier
+pub async fn send(state: &impl State,
+                  params: SummaryParams)
+                  -> Result<Value, AnyError> {
+  state.authenticate_integrate()
+       .await
+       .tap_err(|e| log::error!("Error authenticating to AD: {:#?}", e))?;
+
+  let name = params.template.clone();
+  let summary = Summary::build(state, params, &SystemClock).await?;
+
+  let body = match template(state.read().norm()?, name.as_deref()) {
+    | Ok(Some(template)) => template.render(&summary.context()),
+    | Ok(None) => summary.render(Format::Text),
+    | Err(e) => {
+      log::error!("{}, sending the default summary", e);
+      summary.render(Format::Text)
+    },
+  };
+
+  state.notify(&summary.title, &body).await?;
+
+  noop()
+}
+
+/// Parse the `day` query parameter into the days between today and that day
+fn parse_day(day: &str, today: NaiveDate) -> Result<i64, String> {
+  let as_date = |day| {
+    NaiveDate::parse_from_str(day, "%Y-%m-%d").map(|d| (d - today).num_days())
+  };
+
+  match day.to_lowercase().as_str() {
+    | "today" => Ok(0),
+    | "tomorrow" => Ok(1),
+    | day => day.parse::<i64>()
+                .or_else(|_| as_date(day))
+                .map_err(|_| format!("{:?} is not a day", day)),
+  }
+}
+
+pub const PREVIEW_DOC: Doc =
+  Doc::new("Render a summary without sending it")
+    .query(&[("day", "today (default), tomorrow, YYYY-MM-DD or a day offset"),
+             ("week", "true to show the whole week of that day"),
+             ("tz", "IANA timezone to show times in"),
+             ("format", "text (default), markdown or html"),
+             ("template", "name of a template to render with")])
+    .response(|| serde_json::json!({"type": "string"}))
+    .content_types(&["text/plain", "text/markdown", "text/html"]);
+
+/// Render a summary without sending it.
+///
+/// Doesn't start a sign-in to Integrate AD either, since that pushes
+/// a device code to the notifiers; it fails with a 503 if not signed in.
+pub fn preview<S: State>(state: &S,
+                         req: router::Req)
+                         -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    let clock = SystemClock;
+    let req = req.http;
+
+    ad::require_authed(state).await?;
+
+    let tz = match req.query_param("tz").map(str::parse::<Tz>).transpose() {
+      | Ok(Some(tz)) => tz,
+      | Ok(None) => state.read().norm()?.timezone,
+      | Err(e) => return Err(HttpError::BadRequest(e.to_string()).into()),
+    };
+
+    let day = req.query_param("day").unwrap_or("today");
+    let offset_days = match parse_day(day, Days::new(tz).today(&clock)) {
+      | Ok(offset) => offset,
+      | Err(e) => return Err(HttpError::BadRequest(e).into()),
+    };
+
+    let format = match req.query_param("format")
+                          .map(str::parse::<Format>)
+                          .transpose()
+    {
+      | Ok(format) => format.unwrap_or(Format::Text),
+      | Err(e) => return Err(HttpError::BadRequest(e.to_string()).into()),
+    };
+
+    let week = req.query_param("week") == Some("true");
+
+    let params = match (offset_days, week) {
+      | (0, false) => SummaryParams::today(),
+      | (1, false) => SummaryParams::tomorrow(),
+      | (offset_days, week) => SummaryParams { offset_days,
+                                               span_days: 1,
+                                               week,
+                                               tz: None,
+                                               title: None,
+                                               template: None },
+    };
+
+    // the text format is as pushed, which may be templated
+    let name = req.query_param("template");
+    let template = match (name, format) {
+      | (Some(_), _) | (None, Format::Text) => {
+        match template(state.read().norm()?, name) {
+          | Ok(template) => template,
+          | Err(e) => return Err(HttpError::BadRequest(e).into()),
+        }
+      },
+      | (None, _) => None,
+    };
+
+    let summary = Summary::build(state,
+                                 SummaryParams { tz: Some(tz),
+                                                 ..params },
+                                 &clock).await?;
+
+    let (format, body) = match template {
+      | Some(template) => (Format::Text, template.render(&summary.context())),
+      | None => (format, summary.render(format)),
+    };
+
+    Ok(HttpResponse::new().header("content-type", format.content_type())
+                          .body(body))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::*;
+  use crate::calendar::{Cat, Personal};
+
+  fn event(title: &str, start: DateTime<Utc>, hours: i64) -> Event {
+    Event { cat: Cat::Work,
+            title: title.into(),
+            time_start: start,
+            time_end: start + Duration::hours(hours),
+            location: None,
+            all_day: false }
+  }
+
+  #[test]
+  pub fn html_should_escape_titles() {
+    // ARRANGE
+    let start = Utc.ymd(2021, 4, 8).and_hms(15, 0, 0);
+    let days = Days::new(chrono_tz::America::Denver);
+    let summary =
+      Summary { title: "Today's Events".into(),
+                events: vec![Event { cat: Cat::Personal(Personal::Chore),
+                                     ..event("Dishes & <laundry>",
+                                             start,
+                                             1) }],
+                days,
+                range: days.day(NaiveDate::from_ymd(2021, 4, 8)),
+                work_hours: WorkHours::default(),
+                focus_min: Duration::hours(1) };
+
+    // ACT
+    let html = summary.render(Format::Html);
+    let text = summary.render(Format::Text);
+
+    // ASSERT
+    assert_eq!(html,
+               "<h1>Today's Events</h1><p>Focus: 10:00AM–05:00PM (7h)</p>\
+                <ul><li><strong>Dishes &amp; &lt;laundry&gt;</strong> \
+                (Personal: Chore): 09:00AM - 10:00AM</li></ul>");
+    assert_eq!(text,
+               "Focus: 10:00AM–05:00PM (7h)\n\n\
+                \"Dishes & <laundry>\" (Personal: Chore)\n\
+                09:00AM - 10:00AM\n\n");
+  }
+
+  #[test]
+  pub fn parse_day_should_accept_names_offsets_and_dates() {
+    // ARRANGE
+    let today = NaiveDate::from_ymd(2021, 4, 8);
+
+    // ACT
+    let days =
+      vec!["today", "Tomorrow", "-1", "2021-04-12"].into_iter()
+                                                   .map(|d| parse_day(d, today))
+                                                   .collect::<Vec<_>>();
+
+    // ASSERT
+    assert_eq!(days, vec![Ok(0), Ok(1), Ok(-1), Ok(4)]);
+    assert!(parse_day("someday", today).is_err());
+  }
+
+  #[test]
+  pub fn week_should_group_events_by_day() {
+    // ARRANGE
+    let days = Days::new(chrono_tz::America::Denver);
+    // 9AM Thursday in Denver
+    let thursday = Utc.ymd(2021, 4, 8).and_hms(15, 0, 0);
+    let offsite =
+      Event { all_day: true,
+              ..event("Offsite", Utc.ymd(2021, 4, 9).and_hms(0, 0, 0), 24) };
+    let summary =
+      Summary { title: "Week of Apr 05".into(),
+                events: vec![event("Planning", thursday, 2),
+                             event("1:1",
+                                   thursday + Duration::minutes(90),
+                                   1),
+                             offsite],
+                days,
+                range: days.week(NaiveDate::from_ymd(2021, 4, 8)),
+                work_hours: WorkHours::default(),
+                focus_min: Duration::hours(1) };
+
+    // ACT
+    let text = summary.render(Format::Text);
+    let templated =
+      "{/stats/scheduled}{#each /days}{#if /all_day} {/weekday}{#end}{#end}"
+        .parse::<Template>()
+        .map(|t| t.render(&summary.context()));
+
+    // ASSERT
+    assert!(text.starts_with("Monday 04/05 (nothing scheduled)\n\
+                              Focus: 09:00AM–05:00PM (8h)\n\n\
+                              Tuesday 04/06 (nothing scheduled)\n"));
+    assert!(text.contains("Thursday 04/08 (2.5h scheduled)\n\
+                           Focus: 11:30AM–05:00PM (5.5h)\n\n\
+                           \"Planning\" (Work)\n09:00AM - 11:00AM\n\n\
+                           \"1:1\" (Work)\n10:30AM - 11:30AM\n\n\
+                           Friday 04/09 (nothing scheduled)\n\
+                           All day: Offsite\n\
+                           Focus: 09:00AM–05:00PM (8h)\n\n"));
+    assert!(text.ends_with("Sunday 04/11 (nothing scheduled)\n\n"));
+    assert_eq!(templated, Ok("2.5h Friday".to_string()));
+  }
+
+  #[test]
+  pub fn focus_should_be_long_gaps_in_working_hours() {
+    // ARRANGE
+    // 9AM to 5PM Thursday in Denver
+    let work = (Utc.ymd(2021, 4, 8).and_hms(15, 0, 0),
+                Utc.ymd(2021, 4, 8).and_hms(23, 0, 0));
+    let at = |h: u32, m: u32| Utc.ymd(2021, 4, 8).and_hms(h, m, 0);
+    let early = event("Standup", at(14, 0), 2);
+    let lunch = event("Lunch", at(18, 0), 1);
+    let overlapping = event("1:1", at(18, 30), 1);
+    let short_gap = event("Sync", at(19, 45), 1);
+    let offsite = Event { all_day: true,
+                          ..event("Offsite", at(0, 0), 24) };
+
+    // ACT
+    let free = focus(&[&early, &lunch, &overlapping, &short_gap, &offsite],
+                     work,
+                     Duration::hours(1));
+
+    // ASSERT
+    // 10AM-12PM, then 20:45 (2:45PM) to the end of the day;
+    // the 15 minutes between 1:1 and Sync is too short
+    assert_eq!(free, vec![(at(16, 0), at(18, 0)), (at(20, 45), at(23, 0))]);
+  }
+}
@@ -0,0 +1,97 @@
+//! `POST /webhooks/{name}`, forwarding payloads through the
+//! [`crate::webhook::Rule`] configured under that name.
+
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+
+use super::{auth,
+            github,
+            openapi::{self, Doc},
+            router,
+            HttpError,
+            State};
+use crate::{app::state::Ext, lamb::HttpResponse, prelude::*, AnyError};
+
+pub const DOC: Doc =
+  Doc::new("Forward a payload through the webhook rule with this name")
+    .request(|| json!({"description": "JSON object, or a form"}))
+    .response(openapi::schema::<github::Forwarded>)
+    .security(|| {
+      json!([{"bearer": []}, {"token": []}, {"signature": [], "timestamp": []}])
+    });
+
+pub fn post<S: State>(state: &S,
+                      req: router::Req)
+                      -> BoxFuture<'_, Result<HttpResponse, AnyError>> {
+  Box::pin(async move {
+    let app = state.read().norm()?;
+    let name = req.params
+                  .get("name")
+                  .map(String::as_str)
+                  .unwrap_or_default();
+    let req = req.http;
+
+    let rule = app.webhook_rules.get(name);
+
+    // unknown names are authenticated like rules without a token,
+    // so callers can't find out which names exist without credentials
+    let authed = match rule.and_then(|rule| rule.token.as_deref()) {
+      | Some(token) => {
+        let given = req.header("Authorization")
+                       .and_then(|h| h.strip_prefix("Bearer "))
+                       .or_else(|| req.query_param("token"))
+                       .unwrap_or_default();
+
+        if auth::verify_token(token, given) {
+          Ok(())
+        } else {
+          Err(auth::Error::BadToken)
+        }
+      },
+      | None => auth::authenticate(app, &req, Utc::now()),
+    };
+
+    if let Err(e) = authed {
+      log::warn!("Rejecting webhook {}: {}", name, e);
+      return Ok(HttpError::from(&e).response()
+                                   .header("WWW-Authenticate", "Bearer"));
+    }
+
+    let rule = match rule {
+      | Some(rule) => rule,
+      | None => {
+        let msg = format!("No webhook named {}", name);
+        return Err(HttpError::NotFound(msg).into());
+      },
+    };
+
+    let payload = if req.is_form() {
+      serde_json::to_value(req.body_form())
+    } else {
+      req.body_json::<Value>()
+    };
+
+    let payload = match payload {
+      | Ok(payload) => payload,
+      | Err(e) => {
+        let msg = format!("Invalid payload: {}", e);
+        return Err(HttpError::BadRequest(msg).into());
+      },
+    };
+
+    let forwarded = if rule.matches(&payload) {
+      let (title, body) = rule.render(&payload);
+      log::info!("Forwarding webhook {}: {}", name, title);
+      state.notify(&title, &body).await?;
+      true
+    } else {
+      log::info!("Webhook {} payload did not match filters", name);
+      false
+    };
+
+    HttpResponse::new().body_json(github::Forwarded { success: true,
+                                                      forwarded })
+                       .norm()
+  })
+}
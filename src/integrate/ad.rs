@@ -70,6 +70,53 @@ impl Auth {
     }
   }
 
+  /// Where the user should enter the code while waiting for code auth
+  pub fn code_url(&self) -> Option<&str> {
+    match self {
+      | Self::WaitForCodeAuth { url, .. } => Some(url),
+      | _ => None,
+    }
+  }
+
+  pub fn expires(&self) -> Option<DateTime<Utc>> {
+    match self {
+      | Self::Authed { expires, .. } => Some(*expires),
+      | _ => None,
+    }
+  }
+
+  /// The signed-in user, read from the claims of the ID token
+  pub fn user(&self) -> Option<String> {
+    let id_token = match self {
+      | Self::Authed { id_token, .. } => id_token,
+      | _ => return None,
+    };
+
+    let claims = id_token.split('.').nth(1)?;
+    let claims = base64::decode_config(claims, base64::URL_SAFE_NO_PAD).ok()?;
+    let claims = serde_json::from_slice::<serde_json::Value>(&claims).ok()?;
+
+    ["preferred_username", "email", "name"].iter()
+                                           .find_map(|k| claims.get(k)?.as_str())
+                                           .map(String::from)
+  }
+
+  /// Forget any tokens or pending code
+  pub fn reset(self) -> Self {
+    let Shared { client_id,
+                 login_base_url,
+                 graph_base_url, } = self.shared();
+
+    Auth::NotAuthed { client_id: client_id.to_string(),
+                      login_base_url: login_base_url.to_string(),
+                      graph_base_url: graph_base_url.to_string() }
+  }
+
+  /// Throw away the current auth and begin a new device code flow
+  pub async fn restart(self, reqw: &reqwest::Client) -> Result<Self, Error> {
+    self.reset().start_code_flow(reqw).await
+  }
+
   pub async fn authenticate(self,
                             reqw: &reqwest::Client)
                             -> Result<Self, Error> {
@@ -310,3 +357,30 @@ struct CodeResponse {
   pub user_code: String,
   pub verification_uri: String,
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  pub fn user_should_read_id_token_claims() {
+    // ARRANGE
+    let claims = base64::encode_config(r#"{"preferred_username":"me@example.com"}"#,
+                                       base64::URL_SAFE_NO_PAD);
+    let auth = Auth::Authed { client_id: "client".into(),
+                              login_base_url: "https://login".into(),
+                              graph_base_url: "https://graph".into(),
+                              expires: Utc::now(),
+                              token: "token".into(),
+                              refresh: "refresh".into(),
+                              id_token: format!("header.{}.signature", claims) };
+
+    // ACT
+    let user = auth.user();
+    let signed_out = auth.reset().user();
+
+    // ASSERT
+    assert_eq!(user, Some("me@example.com".to_string()));
+    assert_eq!(signed_out, None);
+  }
+}
@@ -67,6 +67,8 @@ struct CalendarViewResponse {
   start: DateWrapper,
   end: DateWrapper,
   location: Location,
+  #[serde(default)]
+  is_all_day: bool,
 }
 
 impl Into<Event> for CalendarViewResponse {
@@ -77,6 +79,7 @@ impl Into<Event> for CalendarViewResponse {
       time_start: self.start.into(),
       time_end: self.end.into(),
       location: Some(self.location.display_name),
+      all_day: self.is_all_day,
     }
   }
 }
@@ -120,7 +123,8 @@ mod tests {
         "locationType": "default",
         "uniqueId": "https://zoom.us/j/12345",
         "uniqueIdType": "private"
-      }
+      },
+      "isAllDay": false
     }"##;
 
     let expected = CalendarViewResponse {
@@ -136,6 +140,7 @@ mod tests {
       location: Location {
         display_name: "https://zoom.us/j/12345".into(),
       },
+      is_all_day: false,
     };
 
     // ACT
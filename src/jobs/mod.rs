@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::Serialize as Ser;
+
+use crate::{app::state, prelude::*, AnyError};
+
+pub mod refresh_auth;
+pub use refresh_auth::RefreshIntegrateAuth;
+
+/// Jobs aren't started with less than this long left before the lambda's
+/// deadline, so a slow job can't get the whole tick killed.
+const DEADLINE_MARGIN_SECS: i64 = 10;
+
+/// Schedule ticks aren't exact, so a job is considered due
+/// this long before its interval has fully elapsed.
+const TICK_SLACK_MINS: i64 = 1;
+
+/// When a job should run, checked on every `RunJobs` tick
+#[derive(Clone, Copy, Debug)]
+pub enum Schedule {
+  /// Run whenever at least this long has passed since the last run
+  Every(Duration),
+}
+
+impl Schedule {
+  pub fn is_due(&self,
+                last_run: Option<DateTime<Utc>>,
+                now: DateTime<Utc>)
+                -> bool {
+    match (self, last_run) {
+      | (Schedule::Every(_), None) => true,
+      | (Schedule::Every(every), Some(last)) => {
+        now - last >= *every - Duration::minutes(TICK_SLACK_MINS)
+      },
+    }
+  }
+}
+
+#[async_trait]
+pub trait Job<S: Sync>: Send + Sync {
+  /// Unique name, used to track runs and to run the job on demand
+  fn name(&self) -> &'static str;
+
+  fn schedule(&self) -> Schedule;
+
+  async fn run(&self, state: &S) -> Result<(), AnyError>;
+}
+
+/// The outcome of running a job
+#[derive(Debug, Ser, JsonSchema)]
+pub struct Report {
+  pub job: String,
+  pub ok: bool,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+pub struct Registry<S> {
+  jobs: Vec<Box<dyn Job<S>>>,
+}
+
+/// Every job the app knows how to run
+pub fn registry<S: state::Read + state::Modify + Sync>() -> Registry<S> {
+  Registry::new().register(RefreshIntegrateAuth)
+}
+
+impl<S: state::Read + state::Modify + Sync> Registry<S> {
+  pub fn new() -> Self {
+    Self { jobs: vec![] }
+  }
+
+  pub fn register(mut self, job: impl Job<S> + 'static) -> Self {
+    self.jobs.push(Box::from(job) as Box<dyn Job<S>>);
+    self
+  }
+
+  pub fn get(&self, name: &str) -> Option<&dyn Job<S>> {
+    self.jobs
+        .iter()
+        .find(|j| j.name() == name)
+        .map(AsRef::as_ref)
+  }
+
+  /// Run every job whose schedule says it is due, leaving any that
+  /// there's no time left for until the next tick
+  pub async fn run_due(&self,
+                       state: &S,
+                       now: DateTime<Utc>,
+                       deadline: DateTime<Utc>)
+                       -> Result<Vec<Report>, AnyError> {
+    let app = state.read().norm()?;
+
+    let due = self.jobs
+                  .iter()
+                  .filter(|j| {
+                    let last_run = app.job_runs.get(j.name()).cloned();
+                    j.schedule().is_due(last_run, now)
+                  })
+                  .collect::<Vec<_>>();
+
+    log::info!("{} of {} jobs due", due.len(), self.jobs.len());
+
+    let mut reports = vec![];
+    for job in due {
+      if deadline - Utc::now() < Duration::seconds(DEADLINE_MARGIN_SECS) {
+        log::warn!("Out of time, leaving {} and later jobs", job.name());
+        break;
+      }
+
+      reports.push(self.run(state, job.as_ref(), now).await);
+    }
+
+    Ok(reports)
+  }
+
+  /// Run a job regardless of its schedule, recording the run if it succeeds
+  pub async fn run(&self,
+                   state: &S,
+                   job: &dyn Job<S>,
+                   now: DateTime<Utc>)
+                   -> Report {
+    let name = job.name();
+    log::info!("Running job {}", name);
+
+    let result = job.run(state)
+                    .await
+                    .tap_err(|e| log::error!("> job {} failed: {:#?}", name, e))
+                    .and_then(|_| {
+                      state.modify(|mut app| {
+                             app.job_runs.insert(name.to_string(), now);
+                             Ok(app)
+                           })
+                           .norm()
+                    });
+
+    Report { job: name.to_string(),
+             ok: result.is_ok(),
+             error: result.err().map(|e| e.to_string()) }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::*;
+
+  #[test]
+  pub fn every_should_be_due_once_interval_elapsed() {
+    // ARRANGE
+    let schedule = Schedule::Every(Duration::minutes(30));
+    let last = Utc.ymd(2021, 4, 8).and_hms(10, 0, 2);
+
+    // ACT
+    let never_ran = schedule.is_due(None, last);
+    let too_soon = schedule.is_due(Some(last), last + Duration::minutes(15));
+    let next_tick =
+      schedule.is_due(Some(last), Utc.ymd(2021, 4, 8).and_hms(10, 30, 0));
+
+    // ASSERT
+    assert!(never_ran);
+    assert!(!too_soon);
+    assert!(next_tick);
+  }
+}
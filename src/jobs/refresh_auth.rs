@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use chrono::Duration;
+
+use super::{Job, Schedule};
+use crate::{app::{state, state::Ext},
+            AnyError};
+
+/// Keeps the Integrate AD session alive by refreshing its token
+/// (or polling a pending device code) before it expires.
+#[derive(Debug)]
+pub struct RefreshIntegrateAuth;
+
+#[async_trait]
+impl<S: state::Read + state::Modify + Sync> Job<S> for RefreshIntegrateAuth {
+  fn name(&self) -> &'static str {
+    "refresh-integrate-auth"
+  }
+
+  fn schedule(&self) -> Schedule {
+    Schedule::Every(Duration::minutes(30))
+  }
+
+  async fn run(&self, state: &S) -> Result<(), AnyError> {
+    state.authenticate_integrate().await
+  }
+}
@@ -0,0 +1,35 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// The request ID of the invocation being handled, for log lines
+static REQUEST_ID: Mutex<String> = Mutex::new(String::new());
+
+/// What handlers need to know about the invocation they're handling
+#[derive(Clone, Debug)]
+pub struct Context {
+  /// The AWS request ID, as seen in the invocation's CloudWatch logs
+  pub request_id: String,
+
+  /// When the lambda will be stopped
+  pub deadline: DateTime<Utc>,
+}
+
+impl Context {
+  pub fn new(lambda: &netlify_lambda::Context) -> Self {
+    Self { request_id: lambda.request_id.clone(),
+           deadline: Utc.timestamp_millis(lambda.deadline as i64) }
+  }
+
+  /// Make this the invocation whose request ID is logged
+  pub fn make_current(&self) {
+    if let Ok(mut id) = REQUEST_ID.lock() {
+      *id = self.request_id.clone();
+    }
+  }
+
+  /// Request ID of the invocation being handled
+  pub fn current_request_id() -> String {
+    REQUEST_ID.lock().map(|id| id.clone()).unwrap_or_default()
+  }
+}
@@ -0,0 +1,224 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{de::DeserializeOwned as DeOwned, Deserialize, Serialize};
+
+pub type Object = HashMap<String, String>;
+
+/// An HTTP request, normalized from whichever payload format
+/// invoked the lambda (see [`super::payload`])
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(from = "super::payload::Request")]
+pub struct HttpRequest {
+  pub path: String,
+  pub method: HttpMethod,
+  pub headers: Option<Object>,
+  pub query: Option<Object>,
+
+  /// The request body, as the integration sent it
+  /// until [`HttpRequest::decode_body`] is called
+  pub body: Option<Body>,
+
+  /// Whether `body` is still base64-encoded
+  pub base64: bool,
+
+  /// The payload format the request arrived in,
+  /// which the response must be sent back in
+  pub format: Format,
+}
+
+impl HttpRequest {
+  /// Look up a header by name, ignoring case
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers
+        .as_ref()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+  }
+
+  pub fn query_param(&self, name: &str) -> Option<&str> {
+    self.query.as_ref()?.get(name).map(String::as_str)
+  }
+
+  /// Decode a base64-encoded body
+  pub fn decode_body(mut self) -> Result<Self, base64::DecodeError> {
+    if self.base64 {
+      self.body = self.body
+                      .map(|Body(b64)| base64::decode(b64).map(Body))
+                      .transpose()?;
+      self.base64 = false;
+    }
+
+    Ok(self)
+  }
+
+  pub fn body_bytes(&self) -> &[u8] {
+    self.body
+        .as_ref()
+        .map(|b| b.0.as_slice())
+        .unwrap_or_default()
+  }
+
+  #[cfg(test)]
+  pub fn body_text(&self) -> Result<&str, std::str::Utf8Error> {
+    std::str::from_utf8(self.body_bytes())
+  }
+
+  pub fn body_json<T: DeOwned>(&self) -> Result<T, serde_json::Error> {
+    serde_json::from_slice(self.body_bytes())
+  }
+
+  /// Whether the body is `application/x-www-form-urlencoded`
+  pub fn is_form(&self) -> bool {
+    self.header("Content-Type")
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| {
+          ct.trim()
+            .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        })
+        .unwrap_or(false)
+  }
+
+  /// Parse an `application/x-www-form-urlencoded` body
+  pub fn body_form(&self) -> Object {
+    url::form_urlencoded::parse(self.body_bytes()).into_owned()
+                                                  .collect()
+  }
+}
+
+/// Raw bytes of a request body
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Body(pub Vec<u8>);
+
+impl fmt::Debug for Body {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?}", String::from_utf8_lossy(&self.0))
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HttpResponse {
+  #[serde(rename = "statusCode")]
+  pub status: i32,
+  pub headers: Option<Object>,
+  pub body: Option<String>,
+
+  /// Whether `body` is base64-encoded binary data
+  #[serde(rename = "isBase64Encoded", default)]
+  pub base64: bool,
+}
+
+impl HttpResponse {
+  /// Serialize into the response shape expected by `format`
+  pub fn into_value(self,
+                    format: Format)
+                    -> Result<serde_json::Value, serde_json::Error> {
+    super::payload::Response::new(self, format).to_value()
+  }
+
+  pub fn new() -> Self {
+    Self { status: 200,
+           headers: None,
+           body: None,
+           base64: false }
+  }
+
+  pub fn status(mut self, status: impl Into<i32>) -> Self {
+    self.status = status.into();
+    self
+  }
+
+  pub fn body_json(mut self,
+                   body: impl Serialize)
+                   -> Result<Self, serde_json::Error> {
+    serde_json::to_string(&body).map(|json| {
+                                  self.body(json).header("content-type",
+                                                         "application/json")
+                                })
+  }
+
+  pub fn body(mut self, body: impl ToString) -> Self {
+    self.body = Some(body.to_string());
+    self.base64 = false;
+    self
+  }
+
+  /// Respond with binary data, which the integration will base64-decode.
+  ///
+  /// Note that API Gateway REST APIs only decode content types listed in
+  /// the API's `binaryMediaTypes`.
+  #[allow(dead_code)] // no handler serves binary yet
+  pub fn body_binary(mut self,
+                     body: impl AsRef<[u8]>,
+                     content_type: impl ToString)
+                     -> Self {
+    self.body = Some(base64::encode(body));
+    self.base64 = true;
+    self.header("content-type", content_type)
+  }
+
+  pub fn headers(mut self, headers: impl Into<Object>) -> Self {
+    self.headers = Some(headers.into());
+    self
+  }
+
+  pub fn header(mut self, k: impl ToString, v: impl ToString) -> Self {
+    if let None = self.headers.as_ref() {
+      self = self.headers(HashMap::new());
+    }
+
+    self.headers
+        .as_mut()
+        .unwrap()
+        .insert(k.to_string(), v.to_string());
+
+    self
+  }
+}
+
+/// The AWS integration that delivered an HTTP request
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Format {
+  /// API Gateway REST API (payload format 1.0)
+  RestV1,
+
+  /// API Gateway HTTP API (payload format 2.0)
+  HttpV2,
+
+  /// Lambda Function URL (payload format 2.0)
+  FunctionUrl,
+
+  /// Application Load Balancer target group
+  Alb { multi_value: bool },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HttpMethod {
+  Get,
+  Post,
+  Delete,
+  Options,
+
+  #[serde(other)]
+  Other,
+}
+
+impl HttpMethod {
+  /// Whether requests with this method change something
+  pub fn is_mutating(&self) -> bool {
+    matches!(self, Self::Post | Self::Delete)
+  }
+}
+
+impl fmt::Display for HttpMethod {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", match self {
+      | Self::Get => "GET",
+      | Self::Post => "POST",
+      | Self::Delete => "DELETE",
+      | Self::Options => "OPTIONS",
+      | Self::Other => "OTHER",
+    })
+  }
+}
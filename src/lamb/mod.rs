@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+pub mod context;
+pub mod http;
+pub mod payload;
+pub mod queue;
+
+pub use context::Context;
+pub use http::*;
+pub use queue::{SnsEvent, SqsEvent};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Event {
+  Http(HttpRequest),
+  Schedule {
+    kind: ScheduleKind,
+  },
+  ScheduledEvent(ScheduledEvent),
+  Sqs(SqsEvent),
+  Sns(SnsEvent),
+
+  /// A schedule payload whose `kind` isn't a [`ScheduleKind`] this version
+  /// understands
+  UnknownSchedule {
+    kind: serde_json::Value,
+  },
+}
+
+impl Event {
+  pub fn from_value(val: serde_json::Value) -> serde_json::Result<Self> {
+    serde_json::from_value::<Self>(val)
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ScheduleKind {
+  KeepWarm,
+  RunJobs,
+  SummaryToday,
+  SummaryTomorrow,
+
+  /// Events of the Monday-to-Sunday week tomorrow is in, grouped by day;
+  /// sent on Sunday evening, that's the coming week
+  SummaryWeek,
+
+  /// A summary of an arbitrary window of days, e.g.
+  /// `{"kind": {"Summary": {"offset_days": 0, "span_days": 3}}}`
+  Summary(SummaryParams),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SummaryParams {
+  /// Days between today and the first day of the window
+  #[serde(default)]
+  pub offset_days: i64,
+
+  /// Number of days in the window
+  #[serde(default = "SummaryParams::default_span_days")]
+  pub span_days: u32,
+
+  /// Cover the whole Monday-to-Sunday week the first day is in,
+  /// instead of `span_days` days
+  #[serde(default)]
+  pub week: bool,
+
+  /// IANA timezone to render times in, e.g. `America/Denver`
+  #[serde(default)]
+  pub tz: Option<Tz>,
+
+  /// Title of the notification
+  #[serde(default)]
+  pub title: Option<String>,
+
+  /// Name of the template in `App::templates` to render with,
+  /// instead of the one named `summary` (if there is one)
+  #[serde(default)]
+  pub template: Option<String>,
+}
+
+impl SummaryParams {
+  /// The most days a window can start from today, or span
+  pub const MAX_DAYS: i64 = 366;
+
+  fn default_span_days() -> u32 {
+    1
+  }
+
+  /// Why the window these params describe can't be summarized, if it can't
+  pub fn check(&self) -> Result<(), String> {
+    let max = Self::MAX_DAYS;
+
+    if !(-max..=max).contains(&self.offset_days) {
+      Err(format!("Can't start a summary {} days from today, only up to {}",
+                  self.offset_days, max))
+    } else if i64::from(self.span_days) > max {
+      Err(format!("Can't summarize {} days, only up to {}",
+                  self.span_days, max))
+    } else {
+      Ok(())
+    }
+  }
+
+  pub fn today() -> Self {
+    Self { offset_days: 0,
+           span_days: 1,
+           week: false,
+           tz: None,
+           title: Some("Today's Events".into()),
+           template: None }
+  }
+
+  pub fn tomorrow() -> Self {
+    Self { offset_days: 1,
+           span_days: 1,
+           week: false,
+           tz: None,
+           title: Some("Tomorrow's Events".into()),
+           template: None }
+  }
+
+  pub fn week() -> Self {
+    Self { offset_days: 1,
+           span_days: 7,
+           week: true,
+           tz: None,
+           title: None,
+           template: None }
+  }
+}
+
+/// A native EventBridge scheduled event, sent when a rule has no
+/// custom input configured
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScheduledEvent {
+  #[serde(rename = "detail-type")]
+  pub detail_type: ScheduledDetailType,
+
+  /// ARNs of the rules that triggered the event
+  pub resources: Vec<String>,
+
+  pub time: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum ScheduledDetailType {
+  #[serde(rename = "Scheduled Event")]
+  ScheduledEvent,
+}
+
+impl ScheduledEvent {
+  /// Find the kind configured for the rule that triggered this event,
+  /// where `rules` is keyed by either rule ARN or rule name
+  pub fn kind(&self,
+              rules: &HashMap<String, ScheduleKind>)
+              -> Option<ScheduleKind> {
+    self.resources.iter().find_map(|arn| {
+                           let name = arn.rsplit('/').next().unwrap_or(arn);
+                           rules.get(arn).or_else(|| rules.get(name)).cloned()
+                         })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use maplit::hashmap;
+
+  use super::*;
+
+  #[test]
+  pub fn scheduled_event_should_map_rule_name_to_kind() {
+    // ARRANGE
+    let json = r##"{
+      "version": "0",
+      "id": "53dc4d37-cffa-4f76-80c9-8b7d4a4d2eaa",
+      "detail-type": "Scheduled Event",
+      "source": "aws.events",
+      "account": "123456789012",
+      "time": "2021-04-08T14:00:00Z",
+      "region": "us-west-2",
+      "resources": ["arn:aws:events:us-west-2:123456789012:rule/morning-summary"],
+      "detail": {}
+    }"##;
+
+    let rules =
+      hashmap! {"morning-summary".to_string() => ScheduleKind::SummaryToday};
+
+    // ACT
+    let event =
+      serde_json::from_str::<Event>(json).expect("should deserialize");
+
+    // ASSERT
+    match event {
+      | Event::ScheduledEvent(ev) => {
+        assert!(matches!(ev.kind(&rules), Some(ScheduleKind::SummaryToday)))
+      },
+      | other => panic!("expected scheduled event, got {:#?}", other),
+    }
+  }
+
+  #[test]
+  pub fn summary_kind_should_parse_params() {
+    // ARRANGE
+    let json =
+      r#"{"kind": {"Summary": {"span_days": 3, "tz": "America/Denver"}}}"#;
+
+    // ACT
+    let event =
+      serde_json::from_str::<Event>(json).expect("should deserialize");
+
+    // ASSERT
+    match event {
+      | Event::Schedule { kind: ScheduleKind::Summary(params), } => {
+        assert_eq!(params.offset_days, 0);
+        assert_eq!(params.span_days, 3);
+        assert_eq!(params.tz, Some(chrono_tz::America::Denver));
+        assert_eq!(params.title, None);
+      },
+      | other => panic!("expected summary schedule, got {:#?}", other),
+    }
+  }
+
+  #[test]
+  pub fn summary_params_should_reject_far_off_windows() {
+    // ARRANGE
+    let params =
+      |offset_days, span_days| SummaryParams { offset_days,
+                                               span_days,
+                                               ..SummaryParams::today() };
+
+    // ACT
+    let checks = vec![params(-366, 366),
+                      params(367, 1),
+                      params(i64::MIN, 1),
+                      params(999_999_999_999_999_999, 1),
+                      params(0, 367)].into_iter()
+                                     .map(|p| p.check().is_ok())
+                                     .collect::<Vec<_>>();
+
+    // ASSERT
+    assert_eq!(checks, vec![true, false, false, false, false]);
+  }
+
+  #[test]
+  pub fn unknown_kind_should_parse_as_unknown_schedule() {
+    // ACT
+    let unit = serde_json::from_str::<Event>(r#"{"kind": "SummaryYesterday"}"#);
+    let with_params =
+      serde_json::from_str::<Event>(r#"{"kind": {"Digest": {"days": 2}}}"#);
+
+    // ASSERT
+    assert!(matches!(unit, Ok(Event::UnknownSchedule { .. })));
+    assert!(matches!(with_params, Ok(Event::UnknownSchedule { .. })));
+  }
+
+  #[test]
+  pub fn custom_input_should_parse_as_schedule() {
+    // ACT
+    let event = serde_json::from_str::<Event>(r#"{"kind": "KeepWarm"}"#);
+
+    // ASSERT
+    assert!(matches!(event,
+                     Ok(Event::Schedule { kind: ScheduleKind::KeepWarm })));
+  }
+}
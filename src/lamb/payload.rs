@@ -0,0 +1,476 @@
+//! Wire formats for the HTTP integrations that can invoke the lambda.
+//!
+//! Every supported request shape deserializes into [`Request`] and is
+//! normalized into [`HttpRequest`]; [`Response`] does the reverse for
+//! [`HttpResponse`], based on the [`Format`] the request arrived in.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize as De, Serialize as Ser};
+
+use super::http::{Body,
+                  Format,
+                  HttpMethod,
+                  HttpRequest,
+                  HttpResponse,
+                  Object};
+
+type MultiValue = HashMap<String, Vec<String>>;
+
+/// Any of the supported HTTP request payloads.
+///
+/// Variant order matters, since an ALB event is also a valid REST API event
+/// when the load balancer's `requestContext` is ignored.
+#[derive(Debug, De)]
+#[serde(untagged)]
+pub enum Request {
+  Alb(AlbRequest),
+  V2(V2Request),
+  V1(V1Request),
+}
+
+/// API Gateway REST API (payload format 1.0)
+#[derive(Debug, De)]
+#[serde(rename_all = "camelCase")]
+pub struct V1Request {
+  pub path: String,
+  pub http_method: HttpMethod,
+  pub headers: Option<Object>,
+  pub query_string_parameters: Option<Object>,
+  pub body: Option<String>,
+
+  #[serde(default)]
+  pub is_base64_encoded: bool,
+}
+
+/// API Gateway HTTP API and Lambda Function URL (payload format 2.0)
+#[derive(Debug, De)]
+#[serde(rename_all = "camelCase")]
+pub struct V2Request {
+  pub raw_path: String,
+  pub cookies: Option<Vec<String>>,
+  pub headers: Option<Object>,
+  pub query_string_parameters: Option<Object>,
+  pub request_context: V2Context,
+  pub body: Option<String>,
+
+  #[serde(default)]
+  pub is_base64_encoded: bool,
+}
+
+#[derive(Debug, De)]
+#[serde(rename_all = "camelCase")]
+pub struct V2Context {
+  pub http: V2Http,
+  pub stage: Option<String>,
+  pub domain_name: Option<String>,
+}
+
+#[derive(Debug, De)]
+#[serde(rename_all = "camelCase")]
+pub struct V2Http {
+  pub method: HttpMethod,
+}
+
+/// Application Load Balancer target group.
+///
+/// When the target group has multi-value headers enabled, `headers` and
+/// `queryStringParameters` are replaced by their `multiValue*` counterparts.
+/// Query parameters are sent url-encoded.
+#[derive(Debug, De)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbRequest {
+  pub request_context: AlbContext,
+  pub path: String,
+  pub http_method: HttpMethod,
+  pub headers: Option<Object>,
+  pub multi_value_headers: Option<MultiValue>,
+  pub query_string_parameters: Option<Object>,
+  pub multi_value_query_string_parameters: Option<MultiValue>,
+  pub body: Option<String>,
+
+  #[serde(default)]
+  pub is_base64_encoded: bool,
+}
+
+#[derive(Debug, De)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbContext {
+  pub elb: AlbElb,
+}
+
+#[derive(Debug, De)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbElb {
+  pub target_group_arn: String,
+}
+
+/// Base64-encoded bodies are decoded later, by
+/// [`HttpRequest::decode_body`], so an invalid one can be answered with
+/// a 400 instead of failing to match any payload format.
+impl From<Request> for HttpRequest {
+  fn from(req: Request) -> Self {
+    match req {
+      | Request::V1(req) => req.into(),
+      | Request::V2(req) => req.into(),
+      | Request::Alb(req) => req.into(),
+    }
+  }
+}
+
+fn body(raw: Option<String>) -> Option<Body> {
+  raw.map(|b| Body(b.into_bytes()))
+}
+
+impl From<V1Request> for HttpRequest {
+  fn from(req: V1Request) -> Self {
+    HttpRequest { path: req.path,
+                  method: req.http_method,
+                  headers: req.headers,
+                  query: req.query_string_parameters,
+                  body: body(req.body),
+                  base64: req.is_base64_encoded,
+                  format: Format::RestV1 }
+  }
+}
+
+impl From<V2Request> for HttpRequest {
+  fn from(req: V2Request) -> Self {
+    let V2Context { http,
+                    stage,
+                    domain_name, } = req.request_context;
+
+    let format = match domain_name {
+      | Some(d) if d.contains(".lambda-url.") => Format::FunctionUrl,
+      | _ => Format::HttpV2,
+    };
+
+    // named stages are part of `rawPath`, but not of any route
+    let path = match stage.filter(|s| s != "$default") {
+      | Some(stage) => {
+        let prefix = format!("/{}", stage);
+        match req.raw_path.strip_prefix(&prefix) {
+          | Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            rest.to_string()
+          },
+          | _ => req.raw_path,
+        }
+      },
+      | None => req.raw_path,
+    };
+
+    // format 2.0 moves cookies out of the headers
+    let headers = match req.cookies.filter(|cs| !cs.is_empty()) {
+      | Some(cookies) => {
+        let mut headers = req.headers.unwrap_or_default();
+        headers.insert("cookie".into(), cookies.join("; "));
+        Some(headers)
+      },
+      | None => req.headers,
+    };
+
+    HttpRequest { path,
+                  method: http.method,
+                  headers,
+                  query: req.query_string_parameters,
+                  body: body(req.body),
+                  base64: req.is_base64_encoded,
+                  format }
+  }
+}
+
+impl From<AlbRequest> for HttpRequest {
+  fn from(req: AlbRequest) -> Self {
+    let last = |m: MultiValue| {
+      m.into_iter()
+       .filter_map(|(k, vs)| vs.into_iter().last().map(|v| (k, v)))
+       .collect::<Object>()
+    };
+
+    log::debug!("ALB request from target group {}",
+                req.request_context.elb.target_group_arn);
+
+    let multi_value = req.multi_value_headers.is_some();
+
+    let headers = req.multi_value_headers.map(last).or(req.headers);
+    let query = req.multi_value_query_string_parameters
+                   .map(last)
+                   .or(req.query_string_parameters)
+                   .map(|q| {
+                     let raw = q.into_iter()
+                                .map(|(k, v)| format!("{}={}", k, v))
+                                .collect::<Vec<_>>()
+                                .join("&");
+
+                     url::form_urlencoded::parse(raw.as_bytes()).into_owned()
+                                                                .collect()
+                   });
+
+    HttpRequest { path: req.path,
+                  method: req.http_method,
+                  headers,
+                  query,
+                  body: body(req.body),
+                  base64: req.is_base64_encoded,
+                  format: Format::Alb { multi_value } }
+  }
+}
+
+/// Any of the supported HTTP response payloads
+#[derive(Debug, Ser)]
+#[serde(untagged)]
+pub enum Response {
+  Proxy(ProxyResponse),
+  Alb(AlbResponse),
+}
+
+/// Response shape shared by API Gateway (both versions) and Function URLs
+#[derive(Debug, Ser)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyResponse {
+  pub status_code: i32,
+  pub headers: Option<Object>,
+  pub body: Option<String>,
+  pub is_base64_encoded: bool,
+}
+
+#[derive(Debug, Ser)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbResponse {
+  pub status_code: i32,
+  pub status_description: String,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub headers: Option<Object>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub multi_value_headers: Option<MultiValue>,
+
+  pub body: String,
+  pub is_base64_encoded: bool,
+}
+
+impl Response {
+  pub fn new(resp: HttpResponse, format: Format) -> Self {
+    match format {
+      | Format::RestV1 | Format::HttpV2 | Format::FunctionUrl => {
+        Response::Proxy(ProxyResponse { status_code: resp.status,
+                                        headers: resp.headers,
+                                        body: resp.body,
+                                        is_base64_encoded: resp.base64 })
+      },
+      | Format::Alb { multi_value } => {
+        let reason = reqwest::StatusCode::from_u16(resp.status as u16)
+          .ok()
+          .and_then(|s| s.canonical_reason())
+          .unwrap_or("Unknown");
+
+        let (headers, multi_value_headers) = if multi_value {
+          let multi = resp.headers
+                          .unwrap_or_default()
+                          .into_iter()
+                          .map(|(k, v)| (k, vec![v]))
+                          .collect::<MultiValue>();
+          (None, Some(multi))
+        } else {
+          (resp.headers, None)
+        };
+
+        Response::Alb(AlbResponse { status_code: resp.status,
+                                    status_description: format!("{} {}",
+                                                                resp.status,
+                                                                reason),
+                                    headers,
+                                    multi_value_headers,
+                                    body: resp.body.unwrap_or_default(),
+                                    is_base64_encoded: resp.base64 })
+      },
+    }
+  }
+
+  pub fn to_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+    serde_json::to_value(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lamb::Event;
+
+  fn parse_http(json: &str) -> HttpRequest {
+    match serde_json::from_str::<Event>(json).expect("should deserialize") {
+      | Event::Http(req) => req,
+      | other => panic!("expected http event, got {:#?}", other),
+    }
+  }
+
+  #[test]
+  pub fn rest_v1_request_should_normalize() {
+    // ARRANGE
+    let json = r##"{
+      "resource": "/{any+}",
+      "path": "/execute",
+      "httpMethod": "POST",
+      "headers": { "Content-Type": "application/json" },
+      "queryStringParameters": null,
+      "requestContext": { "stage": "dev", "httpMethod": "POST" },
+      "body": "{}",
+      "isBase64Encoded": false
+    }"##;
+
+    // ACT
+    let req = parse_http(json);
+
+    // ASSERT
+    assert_eq!(req.format, Format::RestV1);
+    assert_eq!(req.method, HttpMethod::Post);
+    assert_eq!(req.path, "/execute");
+    assert_eq!(req.header("content-type"), Some("application/json"));
+  }
+
+  #[test]
+  pub fn http_v2_request_should_normalize() {
+    // ARRANGE
+    let json = r##"{
+      "version": "2.0",
+      "routeKey": "ANY /{any+}",
+      "rawPath": "/dev/jobs/refresh",
+      "rawQueryString": "force=true",
+      "cookies": ["a=1", "b=2"],
+      "headers": { "content-type": "application/json" },
+      "queryStringParameters": { "force": "true" },
+      "requestContext": {
+        "domainName": "abc123.execute-api.us-west-2.amazonaws.com",
+        "stage": "dev",
+        "http": { "method": "GET", "path": "/dev/jobs/refresh" }
+      },
+      "isBase64Encoded": false
+    }"##;
+
+    // ACT
+    let req = parse_http(json);
+
+    // ASSERT
+    assert_eq!(req.format, Format::HttpV2);
+    assert_eq!(req.method, HttpMethod::Get);
+    assert_eq!(req.path, "/jobs/refresh");
+    assert_eq!(req.header("Cookie"), Some("a=1; b=2"));
+    assert_eq!(req.query.unwrap().get("force").map(String::as_str),
+               Some("true"));
+  }
+
+  #[test]
+  pub fn function_url_request_should_normalize() {
+    // ARRANGE
+    let json = r##"{
+      "version": "2.0",
+      "rawPath": "/execute",
+      "rawQueryString": "",
+      "headers": {},
+      "requestContext": {
+        "domainName": "abc123.lambda-url.us-west-2.on.aws",
+        "stage": "$default",
+        "http": { "method": "POST", "path": "/execute" }
+      },
+      "isBase64Encoded": false
+    }"##;
+
+    // ACT
+    let req = parse_http(json);
+
+    // ASSERT
+    assert_eq!(req.format, Format::FunctionUrl);
+    assert_eq!(req.path, "/execute");
+  }
+
+  #[test]
+  pub fn alb_request_should_normalize() {
+    // ARRANGE
+    let json = r##"{
+      "requestContext": {
+        "elb": { "targetGroupArn": "arn:aws:elasticloadbalancing:us-west-2:123:targetgroup/hooks/abc" }
+      },
+      "httpMethod": "GET",
+      "path": "/events",
+      "multiValueQueryStringParameters": { "from": ["2021-04-08T00%3A00%3A00Z"] },
+      "multiValueHeaders": { "accept": ["application/json"] },
+      "body": "",
+      "isBase64Encoded": false
+    }"##;
+
+    // ACT
+    let req = parse_http(json);
+
+    // ASSERT
+    assert_eq!(req.format, Format::Alb { multi_value: true });
+    assert_eq!(req.header("Accept"), Some("application/json"));
+    assert_eq!(req.query.unwrap().get("from").map(String::as_str),
+               Some("2021-04-08T00:00:00Z"));
+  }
+
+  #[test]
+  pub fn base64_body_should_be_decoded() {
+    // ARRANGE
+    let json = r##"{
+      "path": "/webhooks/form",
+      "httpMethod": "POST",
+      "headers": { "Content-Type": "application/x-www-form-urlencoded" },
+      "body": "dGl0bGU9SGVsbG8lMjB0aGVyZSZib2R5PWhp",
+      "isBase64Encoded": true
+    }"##;
+
+    // ACT
+    let req = parse_http(json).decode_body().expect("should decode");
+    let form = req.body_form();
+
+    // ASSERT
+    assert_eq!(req.body_text(), Ok("title=Hello%20there&body=hi"));
+    assert_eq!(form.get("title").map(String::as_str), Some("Hello there"));
+  }
+
+  #[test]
+  pub fn invalid_base64_body_should_fail_to_decode() {
+    // ARRANGE
+    let json = r##"{
+      "path": "/webhooks/form",
+      "httpMethod": "POST",
+      "body": "not base64!",
+      "isBase64Encoded": true
+    }"##;
+
+    // ACT
+    let req = parse_http(json);
+
+    // ASSERT
+    assert!(req.decode_body().is_err());
+  }
+
+  #[test]
+  pub fn binary_response_should_be_flagged() {
+    // ARRANGE
+    let resp =
+      HttpResponse::new().body_binary(b"BEGIN:VCALENDAR", "text/calendar");
+
+    // ACT
+    let json = resp.into_value(Format::HttpV2).unwrap();
+
+    // ASSERT
+    assert_eq!(json["isBase64Encoded"], true);
+    assert_eq!(json["body"], base64::encode("BEGIN:VCALENDAR"));
+  }
+
+  #[test]
+  pub fn alb_response_should_include_status_description() {
+    // ARRANGE
+    let resp = HttpResponse::new().status(404)
+                                  .header("content-type", "text/plain");
+
+    // ACT
+    let json = resp.into_value(Format::Alb { multi_value: true }).unwrap();
+
+    // ASSERT
+    assert_eq!(json["statusDescription"], "404 Not Found");
+    assert_eq!(json["multiValueHeaders"]["content-type"][0], "text/plain");
+  }
+}
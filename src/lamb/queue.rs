@@ -0,0 +1,165 @@
+//! SQS and SNS events, which let other systems push messages to the app
+//! instead of calling it over HTTP.
+//!
+//! Message bodies are JSON [`Message`]s, e.g.
+//! `{"type": "Notify", "title": "Deploy", "body": "api v2 is live"}` or
+//! `{"type": "Job", "name": "refresh-integrate-auth"}`.
+
+use serde::{Deserialize, Serialize};
+
+/// A batch of messages from an SQS queue
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SqsEvent {
+  #[serde(rename = "Records")]
+  pub records: Vec<SqsRecord>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqsRecord {
+  pub message_id: String,
+  pub body: String,
+  pub event_source: SqsSource,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum SqsSource {
+  #[serde(rename = "aws:sqs")]
+  Sqs,
+}
+
+/// Messages published to an SNS topic, one per record
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SnsEvent {
+  #[serde(rename = "Records")]
+  pub records: Vec<SnsRecord>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SnsRecord {
+  pub event_source: SnsSource,
+  pub sns: SnsMessage,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum SnsSource {
+  #[serde(rename = "aws:sns")]
+  Sns,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SnsMessage {
+  pub message_id: String,
+  pub message: String,
+
+  #[serde(default)]
+  pub subject: Option<String>,
+}
+
+/// What a queued message asks the app to do
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum Message {
+  /// Send a notification through every configured notifier
+  Notify { title: String, body: String },
+
+  /// Run a job now, regardless of its schedule
+  Job { name: String },
+}
+
+impl SqsRecord {
+  pub fn message(&self) -> serde_json::Result<Message> {
+    serde_json::from_str(&self.body)
+  }
+}
+
+impl SnsMessage {
+  /// Parse the message, treating a plain-text message with a subject
+  /// (e.g. from a CloudWatch alarm) as a notification
+  pub fn message(&self) -> serde_json::Result<Message> {
+    serde_json::from_str(&self.message).or_else(|e| match &self.subject {
+      | Some(subject) => Ok(Message::Notify { title: subject.clone(),
+                                              body: self.message.clone() }),
+      | None => Err(e),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lamb::Event;
+
+  #[test]
+  pub fn sqs_batch_should_parse_messages() {
+    // ARRANGE
+    let json = r#"{
+      "Records": [{
+        "messageId": "059f36b4-87a3-44ab-83d2-661975830a7d",
+        "receiptHandle": "AQEBwJnKyrHigUMZj6rYigCgxlaS3SLy0a...",
+        "body": "{\"type\": \"Notify\", \"title\": \"Deploy\", \"body\": \"api is live\"}",
+        "attributes": {"ApproximateReceiveCount": "1"},
+        "messageAttributes": {},
+        "md5OfBody": "e4e68fb7bd0e697a0ae8f1bb342846b3",
+        "eventSource": "aws:sqs",
+        "eventSourceARN": "arn:aws:sqs:us-west-2:123456789012:hooks",
+        "awsRegion": "us-west-2"
+      }, {
+        "messageId": "2e1424d4-f796-459a-8184-9c92662be6da",
+        "body": "{\"type\": \"Job\", \"name\": \"refresh-integrate-auth\"}",
+        "eventSource": "aws:sqs"
+      }]
+    }"#;
+
+    // ACT
+    let event =
+      serde_json::from_str::<Event>(json).expect("should deserialize");
+
+    // ASSERT
+    match event {
+      | Event::Sqs(ev) => {
+        assert!(matches!(ev.records[0].message(),
+                         Ok(Message::Notify { title, .. }) if title == "Deploy"));
+        assert!(matches!(ev.records[1].message(),
+                         Ok(Message::Job { name }) if name == "refresh-integrate-auth"));
+      },
+      | other => panic!("expected sqs event, got {:#?}", other),
+    }
+  }
+
+  #[test]
+  pub fn sns_plain_message_with_subject_should_notify() {
+    // ARRANGE
+    let json = r#"{
+      "Records": [{
+        "EventSource": "aws:sns",
+        "EventVersion": "1.0",
+        "EventSubscriptionArn": "arn:aws:sns:us-west-2:123456789012:alarms:2bcfbf39",
+        "Sns": {
+          "Type": "Notification",
+          "MessageId": "95df01b4-ee98-5cb9-9903-4c221d41eb5e",
+          "TopicArn": "arn:aws:sns:us-west-2:123456789012:alarms",
+          "Subject": "ALARM: api 5xx",
+          "Message": "Threshold crossed",
+          "Timestamp": "2021-04-08T14:00:00.000Z"
+        }
+      }]
+    }"#;
+
+    // ACT
+    let event =
+      serde_json::from_str::<Event>(json).expect("should deserialize");
+
+    // ASSERT
+    match event {
+      | Event::Sns(ev) => {
+        assert!(matches!(ev.records[0].sns.message(),
+                         Ok(Message::Notify { title, body })
+                         if title == "ALARM: api 5xx" && body == "Threshold crossed"));
+      },
+      | other => panic!("expected sns event, got {:#?}", other),
+    }
+  }
+}
@@ -5,9 +5,13 @@ mod app;
 mod calendar;
 mod handle;
 mod integrate;
+mod jobs;
 mod lamb;
 mod notify;
 mod prelude;
+mod template;
+mod time;
+mod webhook;
 
 use app::{state,
           state::{Ext, Read}};
@@ -17,8 +21,11 @@ type AnyError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 #[lambda]
 #[tokio::main]
-async fn main(event_raw: Value, _: Context) -> Result<Value, AnyError> {
-  use lamb::{Event::*, ScheduleKind::*};
+async fn main(event_raw: Value, raw_ctx: Context) -> Result<Value, AnyError> {
+  use lamb::Event::*;
+
+  let ctx = lamb::Context::new(&raw_ctx);
+  ctx.make_current();
 
   init_logger()?;
 
@@ -37,37 +44,59 @@ async fn main(event_raw: Value, _: Context) -> Result<Value, AnyError> {
     }
   }
 
+  // SNS invokes the lambda asynchronously, so failed SNS events are
+  // retried if the error is returned
+  let retry = matches!(event, Sns(_));
+
   let handle_result = match event {
-    | Http(req) => handle::http(&s(), req).await,
-    | Schedule { kind } => handle::summary(&s(), kind).await,
-    | _ => handle::noop(),
+    | Http(req) => handle::http(&s(), req, &ctx).await,
+    | Schedule { kind } => handle::schedule(&s(), kind, &ctx).await,
+    | ScheduledEvent(ev) => handle::scheduled_event(&s(), ev, &ctx).await,
+    | Sqs(ev) => handle::queue::sqs(&s(), ev, &ctx).await,
+    | Sns(ev) => handle::queue::sns(&s(), ev, &ctx).await,
+    | UnknownSchedule { kind } => handle::unknown_schedule(kind),
   };
 
   if let Err(err) = handle_result {
     log::error!("{:#?}", err);
 
-    s().notify("error", &format!("{}", err))
+    let msg = format!("{}\n\nRequest ID: {}", err, ctx.request_id);
+    s().notify("error", &msg)
        .await
        .tap_err(|e| log::error!("Failed to read app state: {:#?}", e))?;
 
-    handle::noop()
+    if retry {
+      Err(err)
+    } else {
+      handle::noop()
+    }
   } else {
     handle_result
   }
 }
 
+/// Set up logging, once per lambda instance
 fn init_logger() -> Result<(), fern::InitError> {
-  fern::Dispatch::new().format(|out, message, record| {
-                         out.finish(format_args!(
-      "{}[{}][{}] {}",
-      chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
-      record.target(),
-      record.level(),
-      message
-    ))
-                       })
-                       .level(log::LevelFilter::Info)
-                       .chain(std::io::stdout())
-                       .apply()?;
-  Ok(())
+  static INIT: std::sync::Once = std::sync::Once::new();
+
+  let mut result = Ok(());
+  INIT.call_once(|| {
+    result = fern::Dispatch::new()
+      .format(|out, message, record| {
+        out.finish(format_args!(
+          "{}[{}][{}][{}] {}",
+          chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
+          lamb::Context::current_request_id(),
+          record.target(),
+          record.level(),
+          message
+        ))
+      })
+      .level(log::LevelFilter::Info)
+      .chain(std::io::stdout())
+      .apply()
+      .map_err(fern::InitError::from);
+  });
+
+  result
 }
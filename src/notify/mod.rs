@@ -1,4 +1,6 @@
 use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize as De, Serialize as Ser};
 use thiserror::Error as DeriveError;
 
 pub mod pushbullet;
@@ -9,11 +11,52 @@ pub struct Slack;
 
 #[async_trait]
 pub trait Notifier {
+  /// Name used to target this notifier, e.g. in `POST /notify`
+  fn name(&self) -> &'static str;
+
   async fn notify(&self,
                   reqw: &reqwest::Client,
                   title: &str,
                   body: &str)
                   -> Result<(), Error>;
+
+  /// Notify with a priority. Notifiers that have no notion of priority
+  /// flag high priority notifications in the title.
+  async fn notify_priority(&self,
+                           reqw: &reqwest::Client,
+                           title: &str,
+                           body: &str,
+                           priority: Priority)
+                           -> Result<(), Error> {
+    match priority {
+      | Priority::High => {
+        self.notify(reqw, &format!("(!) {}", title), body).await
+      },
+      | Priority::Low | Priority::Normal => {
+        self.notify(reqw, title, body).await
+      },
+    }
+  }
+}
+
+/// How urgently a notification needs attention
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Ser, De, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+  Low,
+  #[default]
+  Normal,
+  High,
+}
+
+/// The outcome of sending a notification through one notifier
+#[derive(Debug, Ser, JsonSchema)]
+pub struct Delivery {
+  pub notifier: String,
+  pub ok: bool,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
 }
 
 #[derive(Debug, DeriveError)]
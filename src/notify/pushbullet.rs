@@ -90,6 +90,10 @@ impl Pushbullet {
 
 #[async_trait]
 impl super::Notifier for Pushbullet {
+  fn name(&self) -> &'static str {
+    "pushbullet"
+  }
+
   async fn notify(&self,
                   reqw: &reqwest::Client,
                   title: &str,
@@ -0,0 +1,285 @@
+//! A small template language for user-defined message layouts, rendered
+//! against a JSON context.
+//!
+//! - `{/json/pointer}` is replaced with the value at that [JSON pointer],
+//!   or nothing if there isn't one. `{.}` is the value in scope itself.
+//! - `{#each /pointer}...{#end}` repeats its body for each item of an
+//!   array. Pointers in the body look in the item first, then in
+//!   `/@index`, `/@first` and `/@last`, then in the enclosing scopes.
+//! - `{#if /pointer}...{#else}...{#end}` renders its first part if the
+//!   value is there and isn't `false`, `0`, `""`, `[]` or `{}`, and the
+//!   (optional) second part otherwise.
+//! - `{{` and `}}` are literal braces.
+//!
+//! ```text
+//! {#each /days}{/label}: {/scheduled_hours}h
+//! {#each /events}- {/title}{#if /location} @ {/location}{#end}
+//! {#end}{#end}
+//! ```
+//!
+//! [JSON pointer]: https://tools.ietf.org/html/rfc6901
+
+use std::{convert::TryFrom, str::FromStr};
+
+use serde::{Deserialize as De, Serialize as Ser};
+use serde_json::{json, Value};
+use thiserror::Error as DeriveError;
+
+#[derive(Debug, PartialEq, DeriveError)]
+pub enum Error {
+  #[error("{{{0}}} at byte {1} is not a value or block")]
+  BadTag(String, usize),
+
+  #[error("{{#{0}}} at byte {1} is never closed with {{#end}}")]
+  Unclosed(String, usize),
+
+  #[error("{{#{0}}} at byte {1} is outside of a block")]
+  Unopened(String, usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+  Text(String),
+  Value(String),
+  Each(String, Vec<Node>),
+  If(String, Vec<Node>, Vec<Node>),
+}
+
+/// A parsed template, (de)serialized as its source
+#[derive(Clone, Debug, PartialEq, Ser, De)]
+#[serde(try_from = "String", into = "String")]
+pub struct Template {
+  src: String,
+  nodes: Vec<Node>,
+}
+
+/// A block being parsed
+struct Open {
+  tag: String,
+  at: usize,
+  pointer: String,
+  body: Vec<Node>,
+  otherwise: Option<Vec<Node>>,
+}
+
+impl Open {
+  fn nodes(&mut self) -> &mut Vec<Node> {
+    match &mut self.otherwise {
+      | Some(nodes) => nodes,
+      | None => &mut self.body,
+    }
+  }
+
+  fn close(self) -> Node {
+    match self.tag.as_str() {
+      | "each" => Node::Each(self.pointer, self.body),
+      | _ => {
+        Node::If(self.pointer, self.body, self.otherwise.unwrap_or_default())
+      },
+    }
+  }
+}
+
+impl FromStr for Template {
+  type Err = Error;
+
+  fn from_str(src: &str) -> Result<Self, Self::Err> {
+    let mut root = Vec::<Node>::new();
+    let mut open = Vec::<Open>::new();
+    let mut text = String::new();
+    let mut rest = src;
+
+    macro_rules! nodes {
+      () => {
+        match open.last_mut() {
+          | Some(block) => block.nodes(),
+          | None => &mut root,
+        }
+      };
+    }
+
+    while let Some(ix) = rest.find(&['{', '}'][..]) {
+      let at = src.len() - rest.len() + ix;
+      text.push_str(&rest[..ix]);
+      let (brace, after) = rest[ix..].split_at(1);
+
+      let end = match (brace, after.find('}')) {
+        | (_, _) if after.starts_with(brace) => {
+          // escaped brace
+          text.push_str(brace);
+          rest = &after[1..];
+          continue;
+        },
+        | ("{", Some(end)) => end,
+        | _ => {
+          text.push_str(brace);
+          rest = after;
+          continue;
+        },
+      };
+
+      let tag = &after[..end];
+      rest = &after[end + 1..];
+
+      if !text.is_empty() {
+        nodes!().push(Node::Text(std::mem::take(&mut text)));
+      }
+
+      let mut words = tag.splitn(2, ' ');
+      match (words.next().unwrap_or_default(), words.next()) {
+        | (".", None) => nodes!().push(Node::Value(String::new())),
+        | (p, None) if p.starts_with('/') => {
+          nodes!().push(Node::Value(p.to_string()))
+        },
+        | (block @ ("#each" | "#if"), Some(p)) if p.starts_with('/') => {
+          open.push(Open { tag: block[1..].to_string(),
+                           at,
+                           pointer: p.to_string(),
+                           body: vec![],
+                           otherwise: None })
+        },
+        | ("#else", None) => match open.last_mut() {
+          | Some(block) if block.tag == "if" && block.otherwise.is_none() => {
+            block.otherwise = Some(vec![])
+          },
+          | _ => return Err(Error::Unopened("else".into(), at)),
+        },
+        | ("#end", None) => match open.pop() {
+          | Some(block) => nodes!().push(block.close()),
+          | None => return Err(Error::Unopened("end".into(), at)),
+        },
+        | _ => return Err(Error::BadTag(tag.to_string(), at)),
+      }
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+      nodes!().push(Node::Text(text));
+    }
+
+    match open.pop() {
+      | Some(block) => Err(Error::Unclosed(block.tag, block.at)),
+      | None => Ok(Self { src: src.to_string(),
+                          nodes: root }),
+    }
+  }
+}
+
+impl TryFrom<String> for Template {
+  type Error = Error;
+
+  fn try_from(src: String) -> Result<Self, Self::Error> {
+    src.parse()
+  }
+}
+
+impl From<Template> for String {
+  fn from(template: Template) -> Self {
+    template.src
+  }
+}
+
+impl Template {
+  pub fn render(&self, context: &Value) -> String {
+    let mut out = String::new();
+    render(&self.nodes, &mut vec![context.clone()], &mut out);
+    out
+  }
+}
+
+/// The value a pointer points to in the innermost scope that has one
+fn lookup<'a>(scopes: &'a [Value], pointer: &str) -> Option<&'a Value> {
+  scopes.iter()
+        .rev()
+        .find_map(|scope| scope.pointer(pointer).filter(|v| !v.is_null()))
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+  match value {
+    | None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+    | Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+    | Some(Value::String(s)) => !s.is_empty(),
+    | Some(Value::Array(vs)) => !vs.is_empty(),
+    | Some(Value::Object(o)) => !o.is_empty(),
+    | Some(Value::Bool(true)) => true,
+  }
+}
+
+fn render(nodes: &[Node], scopes: &mut Vec<Value>, out: &mut String) {
+  for node in nodes {
+    match node {
+      | Node::Text(text) => out.push_str(text),
+      | Node::Value(pointer) => match lookup(scopes, pointer) {
+        | Some(Value::String(s)) => out.push_str(s),
+        | Some(v) => out.push_str(&v.to_string()),
+        | None => (),
+      },
+      | Node::If(pointer, then, otherwise) => {
+        if truthy(lookup(scopes, pointer)) {
+          render(then, scopes, out)
+        } else {
+          render(otherwise, scopes, out)
+        }
+      },
+      | Node::Each(pointer, body) => {
+        let items = match lookup(scopes, pointer) {
+          | Some(Value::Array(items)) => items.clone(),
+          | _ => continue,
+        };
+
+        for (ix, item) in items.iter().enumerate() {
+          scopes.push(json!({
+                        "@index": ix,
+                        "@first": ix == 0,
+                        "@last": ix + 1 == items.len(),
+                      }));
+          scopes.push(item.clone());
+          render(body, scopes, out);
+          scopes.truncate(scopes.len() - 2);
+        }
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  pub fn render_should_loop_and_branch() {
+    // ARRANGE
+    let template = "{{{/tz}}}\n\
+                    {#each /days}{/label}:\
+                    {#each /events} {/title}{#if /@last}.{#else},{#end}{#end}\
+                    {#if /events}{#else} nothing in {/tz}{#end}\n\
+                    {#end}";
+    let context = json!({
+      "tz": "America/Denver",
+      "days": [{"label": "Mon", "events": [{"title": "a"}, {"title": "b"}]},
+               {"label": "Tue", "events": []}],
+    });
+
+    // ACT
+    let rendered = template.parse::<Template>()
+                           .map(|t| t.render(&context))
+                           .expect("should parse");
+
+    // ASSERT
+    assert_eq!(rendered,
+               "{America/Denver}\nMon: a, b.\nTue: nothing in America/Denver\n");
+  }
+
+  #[test]
+  pub fn parse_should_reject_unbalanced_blocks() {
+    // ACT
+    let unclosed = "{#each /days}{/label}".parse::<Template>();
+    let unopened = "{/label}{#end}".parse::<Template>();
+    let bad = "{title}".parse::<Template>();
+
+    // ASSERT
+    assert_eq!(unclosed, Err(Error::Unclosed("each".into(), 0)));
+    assert_eq!(unopened, Err(Error::Unopened("end".into(), 8)));
+    assert_eq!(bad, Err(Error::BadTag("title".into(), 0)));
+  }
+}
@@ -0,0 +1,267 @@
+//! Local calendar days, and the UTC instants they start and end at.
+//!
+//! Days are counted in a timezone (usually `App::timezone`), so "today"
+//! means the user's today, and a day is exactly as long as it is locally:
+//! 23 or 25 hours when DST starts or ends. What "now" is comes from a
+//! [`Clock`], so code asking for today can be tested at any time.
+
+use std::str::FromStr;
+
+use chrono::{DateTime,
+             Datelike,
+             Duration,
+             NaiveDate,
+             NaiveTime,
+             TimeZone,
+             Utc,
+             Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize as De, Serialize as Ser};
+
+/// Where the current time comes from
+pub trait Clock {
+  fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> DateTime<Utc> {
+    Utc::now()
+  }
+}
+
+/// A clock stopped at one instant, for tests
+#[cfg(test)]
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+  fn now(&self) -> DateTime<Utc> {
+    self.0
+  }
+}
+
+/// A run of whole local days
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+  /// The first day in the range
+  pub first: NaiveDate,
+
+  /// The day after the last day in the range
+  pub until: NaiveDate,
+
+  /// When the first day starts
+  pub start: DateTime<Utc>,
+
+  /// When the last day ends (and the day after starts)
+  pub end: DateTime<Utc>,
+}
+
+impl Range {
+  /// The last day in the range
+  pub fn last(&self) -> NaiveDate {
+    self.until.pred()
+  }
+
+  /// Every day in the range
+  pub fn days(&self) -> impl Iterator<Item = NaiveDate> {
+    let first = self.first;
+    (0..(self.until - first).num_days()).map(move |n| first + Duration::days(n))
+  }
+}
+
+/// Calendar days as counted in a timezone
+#[derive(Clone, Copy, Debug)]
+pub struct Days {
+  pub tz: Tz,
+}
+
+impl Days {
+  pub fn new(tz: Tz) -> Self {
+    Self { tz }
+  }
+
+  /// The local date at an instant
+  pub fn date(&self, dt: DateTime<Utc>) -> NaiveDate {
+    dt.with_timezone(&self.tz).date().naive_local()
+  }
+
+  pub fn today(&self, clock: &impl Clock) -> NaiveDate {
+    self.date(clock.now())
+  }
+
+  /// When a local day starts: midnight, unless a DST change skips
+  /// midnight that day
+  pub fn start(&self, day: NaiveDate) -> DateTime<Utc> {
+    (0..24).find_map(|h| {
+             self.tz
+                 .from_local_datetime(&day.and_hms(h, 0, 0))
+                 .earliest()
+           })
+           .map(|dt| dt.with_timezone(&Utc))
+           .expect("every day has a local hour")
+  }
+
+  /// When it's a local time of day on a day, or an hour later if a DST
+  /// change skips that time
+  pub fn at(&self, day: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+    let local = day.and_time(time);
+    self.tz
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+          self.tz
+              .from_local_datetime(&(local + Duration::hours(1)))
+              .earliest()
+        })
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| self.start(day))
+  }
+
+  /// `count` days starting with `first`
+  pub fn range(&self, first: NaiveDate, count: u32) -> Range {
+    let until = first + Duration::days(i64::from(count));
+
+    Range { first,
+            until,
+            start: self.start(first),
+            end: self.start(until) }
+  }
+
+  pub fn day(&self, day: NaiveDate) -> Range {
+    self.range(day, 1)
+  }
+
+  /// The Monday-to-Sunday week a day is in
+  pub fn week(&self, day: NaiveDate) -> Range {
+    let since_monday = day.weekday().num_days_from_monday();
+    self.range(day - Duration::days(i64::from(since_monday)), 7)
+  }
+}
+
+/// The local hours of a weekday that are for work, e.g. `09:00-17:00`.
+///
+/// Weekends have no working hours.
+#[derive(Clone, Copy, Debug, PartialEq, Ser, De)]
+pub struct WorkHours {
+  pub start: NaiveTime,
+  pub end: NaiveTime,
+}
+
+impl Default for WorkHours {
+  fn default() -> Self {
+    Self { start: NaiveTime::from_hms(9, 0, 0),
+           end: NaiveTime::from_hms(17, 0, 0) }
+  }
+}
+
+impl WorkHours {
+  /// When work starts and ends on a day, if it's a working day
+  pub fn on(&self,
+            days: &Days,
+            day: NaiveDate)
+            -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    match day.weekday() {
+      | Weekday::Sat | Weekday::Sun => None,
+      | _ => Some((days.at(day, self.start), days.at(day, self.end))),
+    }
+  }
+}
+
+impl FromStr for WorkHours {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let time = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M");
+    let err = || format!("{:?} is not working hours like 09:00-17:00", s);
+
+    match s.split_once('-') {
+      | Some((start, end)) => match (time(start), time(end)) {
+        | (Ok(start), Ok(end)) if start < end => Ok(Self { start, end }),
+        | _ => Err(err()),
+      },
+      | None => Err(err()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  pub fn today_should_be_the_local_day() {
+    // ARRANGE
+    let days = Days::new(chrono_tz::America::Denver);
+    // 6PM on the 7th in Denver
+    let clock = FixedClock(Utc.ymd(2021, 4, 8).and_hms(0, 30, 0));
+
+    // ACT
+    let today = days.day(days.today(&clock));
+
+    // ASSERT
+    assert_eq!(today.first, NaiveDate::from_ymd(2021, 4, 7));
+    assert_eq!(today.start, Utc.ymd(2021, 4, 7).and_hms(6, 0, 0));
+    assert_eq!(today.end, Utc.ymd(2021, 4, 8).and_hms(6, 0, 0));
+  }
+
+  #[test]
+  pub fn days_should_follow_dst() {
+    // ARRANGE
+    let denver = Days::new(chrono_tz::America::Denver);
+    let havana = Days::new(chrono_tz::America::Havana);
+
+    // ACT
+    let dst_starts = denver.day(NaiveDate::from_ymd(2021, 3, 14));
+    let dst_ends = denver.day(NaiveDate::from_ymd(2021, 11, 7));
+    let skipped = havana.start(NaiveDate::from_ymd(2021, 3, 14));
+
+    // ASSERT
+    assert_eq!(dst_starts.end - dst_starts.start, Duration::hours(23));
+    assert_eq!(dst_ends.end - dst_ends.start, Duration::hours(25));
+    // Havana skips from midnight to 1AM when DST starts
+    assert_eq!(skipped, Utc.ymd(2021, 3, 14).and_hms(5, 0, 0));
+  }
+
+  #[test]
+  pub fn week_should_run_monday_to_sunday() {
+    // ARRANGE
+    let days = Days::new(chrono_tz::America::Denver);
+
+    // ACT
+    // a Thursday, and a Sunday
+    let week = days.week(NaiveDate::from_ymd(2021, 4, 8));
+    let sunday = days.week(NaiveDate::from_ymd(2021, 4, 11));
+
+    // ASSERT
+    assert_eq!(week.first, NaiveDate::from_ymd(2021, 4, 5));
+    assert_eq!(week.last(), NaiveDate::from_ymd(2021, 4, 11));
+    assert_eq!(week.start, Utc.ymd(2021, 4, 5).and_hms(6, 0, 0));
+    assert_eq!(sunday, week);
+  }
+
+  #[test]
+  pub fn work_hours_should_be_local_and_only_on_weekdays() {
+    // ARRANGE
+    let days = Days::new(chrono_tz::America::Denver);
+
+    // ACT
+    let hours = "09:00-17:30".parse::<WorkHours>();
+    let thursday = hours.clone()
+                        .map(|h| h.on(&days, NaiveDate::from_ymd(2021, 4, 8)));
+    let saturday = hours.clone()
+                        .map(|h| h.on(&days, NaiveDate::from_ymd(2021, 4, 10)));
+
+    // ASSERT
+    assert_eq!(thursday,
+               Ok(Some((Utc.ymd(2021, 4, 8).and_hms(15, 0, 0),
+                        Utc.ymd(2021, 4, 8).and_hms(23, 30, 0)))));
+    assert_eq!(saturday, Ok(None));
+    assert!("17:00-09:00".parse::<WorkHours>().is_err());
+    assert!("9am".parse::<WorkHours>().is_err());
+  }
+}
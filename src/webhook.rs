@@ -0,0 +1,147 @@
+//! Rules turning arbitrary inbound webhooks into notifications,
+//! configured in `App::webhook_rules` and served at `/webhooks/{name}`.
+//!
+//! Titles and bodies are [templates](crate::template) rendered against the
+//! payload, so `{/json/pointer}` is replaced with the value at that
+//! [JSON pointer] and `{#each}`/`{#if}` blocks work too, while filters
+//! decide which payloads are forwarded at all:
+//!
+//! ```json
+//! {"deploys": {
+//!   "title": "Deploy of {/app} {/status}",
+//!   "body": "{/commit/message}\n{/url}",
+//!   "filters": [{"pointer": "/status", "one_of": ["failed", "succeeded"]},
+//!               {"pointer": "/env", "equals": "production"}],
+//!   "token": "s3cret"
+//! }}
+//! ```
+//!
+//! [JSON pointer]: https://tools.ietf.org/html/rfc6901
+
+use serde::{Deserialize as De, Serialize as Ser};
+use serde_json::Value;
+
+use crate::template::Template;
+
+#[derive(Clone, Debug, Ser, De)]
+pub struct Rule {
+  /// Parsed as the rules are loaded, so an invalid template fails there
+  /// rather than on every delivery
+  pub title: Template,
+  pub body: Template,
+
+  /// Every filter must match for a payload to be forwarded
+  #[serde(default)]
+  pub filters: Vec<Filter>,
+
+  /// Token the sender must present as a bearer token or `?token=`.
+  /// Without one, the app's usual inbound authentication applies.
+  #[serde(default)]
+  pub token: Option<String>,
+}
+
+#[derive(Clone, Debug, Ser, De)]
+pub struct Filter {
+  pub pointer: String,
+
+  #[serde(flatten)]
+  pub cond: Cond,
+}
+
+#[derive(Clone, Debug, Ser, De)]
+#[serde(rename_all = "snake_case")]
+pub enum Cond {
+  Equals(Value),
+  NotEquals(Value),
+  OneOf(Vec<Value>),
+
+  /// The value is a string containing this, or an array containing it
+  Contains(String),
+
+  /// Whether the pointer should resolve to a non-null value
+  Exists(bool),
+}
+
+impl Filter {
+  pub fn matches(&self, payload: &Value) -> bool {
+    let value = payload.pointer(&self.pointer).filter(|v| !v.is_null());
+
+    match (&self.cond, value) {
+      | (Cond::Exists(exists), v) => *exists == v.is_some(),
+      | (Cond::Equals(want), Some(v)) => want == v,
+      | (Cond::NotEquals(want), v) => v != Some(want),
+      | (Cond::OneOf(wants), Some(v)) => wants.contains(v),
+      | (Cond::Contains(want), Some(Value::String(s))) => {
+        s.contains(want.as_str())
+      },
+      | (Cond::Contains(want), Some(Value::Array(vs))) => {
+        vs.iter().any(|v| v.as_str() == Some(want.as_str()))
+      },
+      | _ => false,
+    }
+  }
+}
+
+impl Rule {
+  pub fn matches(&self, payload: &Value) -> bool {
+    self.filters.iter().all(|f| f.matches(payload))
+  }
+
+  /// The notification title and body for a payload
+  pub fn render(&self, payload: &Value) -> (String, String) {
+    (self.title.render(payload), self.body.render(payload))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  pub fn rule_should_parse_and_render_templates() {
+    // ARRANGE
+    let rule = serde_json::from_value::<Rule>(json!({
+      "title": "{{{/app}}} #{/build/number} {/missing}{/tags}",
+      "body": "{#each /tags}- {.}\n{#end}",
+    })).expect("should deserialize");
+    let payload = json!({"app": "api", "build": {"number": 7}, "tags": ["a"]});
+
+    // ACT
+    let rendered = rule.render(&payload);
+    let serialized = serde_json::to_value(&rule).expect("should serialize");
+    let invalid =
+      serde_json::from_value::<Rule>(json!({"title": "{app}", "body": ""}));
+
+    // ASSERT
+    assert_eq!(rendered, (r#"{api} #7 ["a"]"#.into(), "- a\n".into()));
+    assert_eq!(serialized["body"], json!("{#each /tags}- {.}\n{#end}"));
+    assert!(invalid.is_err());
+  }
+
+  #[test]
+  pub fn rule_should_match_only_when_every_filter_does() {
+    // ARRANGE
+    let rule = serde_json::from_value::<Rule>(json!({
+      "title": "{/app}",
+      "body": "",
+      "filters": [{"pointer": "/status", "one_of": ["failed", "succeeded"]},
+                  {"pointer": "/env", "equals": "production"},
+                  {"pointer": "/skip", "exists": false}],
+    })).expect("should deserialize");
+
+    // ACT
+    let matched =
+      rule.matches(&json!({"status": "failed", "env": "production"}));
+    let wrong_env =
+      rule.matches(&json!({"status": "failed", "env": "staging"}));
+    let skipped =
+      rule.matches(&json!({"status": "failed", "env": "production", "skip": 1}));
+
+    // ASSERT
+    assert!(matched);
+    assert!(!wrong_env);
+    assert!(!skipped);
+  }
+}